@@ -0,0 +1,236 @@
+use super::*;
+
+/// Every square except those in the leftmost column.
+const NOT_LEFT: u64 = 0xfefe_fefe_fefe_fefe;
+
+/// Every square except those in the rightmost column.
+const NOT_RIGHT: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+
+/// The eight directions a line of flips can run in, as a bit shift and the
+/// mask that removes squares that wrapped around the edge of the board. These
+/// are in the same order that Movement uses for the general case.
+const DIRECTIONS: [(i32, u64); 8] = [
+    (-9, NOT_RIGHT),
+    (-1, NOT_RIGHT),
+    (7, NOT_RIGHT),
+    (-8, !0),
+    (8, !0),
+    (-7, NOT_LEFT),
+    (1, NOT_LEFT),
+    (9, NOT_LEFT),
+];
+
+/// Holds an 8x8 board as a pair of 64-bit masks, one for each color; bit
+/// 'y * 8 + x' is set if that square holds a disc of that color. This
+/// allows valid moves and flips to be found with a few shifts rather than
+/// walking over the board. It also keeps the Zobrist hash of its
+/// discs up to date as they change.
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct Bitboard {
+    black: u64,
+    white: u64,
+    hash: u64,
+}
+
+impl Bitboard {
+    /// The width and height of the board a bitboard can represent.
+    pub const SIZE: usize = 8;
+
+    /// Returns the mask of squares holding 'cell'; for Empty, this
+    /// is the squares holding neither color.
+    pub fn get_mask(&self, cell: Cell) -> u64 {
+        match cell {
+            Cell::Black => self.black,
+            Cell::White => self.white,
+            Cell::Empty => !(self.black | self.white),
+        }
+    }
+
+    /// Stores 'cell' at the location given.
+    pub fn set(&mut self, loc: Loc, cell: Cell) {
+        let bit = Bitboard::bit(loc);
+        let index = bit.trailing_zeros() as usize;
+        let old = if self.black & bit != 0 {
+            Cell::Black
+        } else if self.white & bit != 0 {
+            Cell::White
+        } else {
+            Cell::Empty
+        };
+        self.hash ^= zobrist_key(index, old) ^ zobrist_key(index, cell);
+
+        self.black &= !bit;
+        self.white &= !bit;
+
+        match cell {
+            Cell::Black => self.black |= bit,
+            Cell::White => self.white |= bit,
+            Cell::Empty => {}
+        }
+    }
+
+    /// The Zobrist hash of the discs on the board; this is the
+    /// same as the hash of the equivalent Board.
+    pub fn get_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the mask of squares where 'cell' has a valid move.
+    pub fn valid_moves(&self, cell: Cell) -> u64 {
+        let (player, opponent) = self.sides(cell);
+        let empty = self.get_mask(Cell::Empty);
+        let mut moves = 0;
+
+        for &dir in &DIRECTIONS {
+            let mut line = shift(player, dir) & opponent;
+            for _ in 0..5 {
+                line |= shift(line, dir) & opponent;
+            }
+            moves |= shift(line, dir) & empty;
+        }
+
+        moves
+    }
+
+    /// Returns the bitboard that results when 'cell' is placed at 'loc', and
+    /// flips what it should. If this is not a valid move, it places 'cell'
+    /// at 'loc' anyway.
+    pub fn play(&self, cell: Cell, loc: Loc) -> Bitboard {
+        let (player, opponent) = self.sides(cell);
+        let start = Bitboard::bit(loc);
+        let mut flips = start;
+
+        for &dir in &DIRECTIONS {
+            let mut line = 0;
+            let mut here = shift(start, dir);
+
+            while here & opponent != 0 {
+                line |= here;
+                here = shift(here, dir);
+            }
+
+            if here & player != 0 {
+                flips |= line;
+            }
+        }
+
+        let mut next = *self;
+        next.hash ^= Bitboard::hash_change(flips & !start, cell.flipped(), cell);
+        let old_start = if start & opponent != 0 {
+            cell.flipped()
+        } else if start & player != 0 {
+            cell
+        } else {
+            Cell::Empty
+        };
+        next.hash ^= Bitboard::hash_change(start, old_start, cell);
+
+        match cell {
+            Cell::Black => {
+                next.black |= flips;
+                next.white &= !flips;
+            }
+            Cell::White => {
+                next.white |= flips;
+                next.black &= !flips;
+            }
+            Cell::Empty => {}
+        }
+        next
+    }
+
+    /// Returns the locations that would be flipped if 'cell' were placed
+    /// at 'loc'; these are ordered by direction, then outwards from 'loc'.
+    pub fn flipped_locations(&self, cell: Cell, loc: Loc) -> Vec<Loc> {
+        let mut locs = Vec::new();
+        self.find_flips(cell, loc, |bit| locs.push(Bitboard::loc(bit)));
+        locs
+    }
+
+    /// Returns an iterator over the locations whose bits are set in 'mask'.
+    pub fn locations(mask: u64) -> impl Iterator<Item = Loc> {
+        let mut remaining = mask;
+        std::iter::from_fn(move || {
+            if remaining == 0 {
+                None
+            } else {
+                let bit = remaining & remaining.wrapping_neg();
+                remaining &= !bit;
+                Some(Bitboard::loc(bit))
+            }
+        })
+    }
+
+    /// Calls 'found' with the bit for each square that would be flipped
+    /// if 'cell' were placed at 'loc'.
+    fn find_flips(&self, cell: Cell, loc: Loc, mut found: impl FnMut(u64)) {
+        let (player, opponent) = self.sides(cell);
+        let start = Bitboard::bit(loc);
+
+        if start & (player | opponent) != 0 {
+            return;
+        }
+
+        for &dir in &DIRECTIONS {
+            // A line holds at most seven squares past 'loc'.
+            let mut line = [0u64; Bitboard::SIZE - 1];
+            let mut len = 0;
+            let mut here = shift(start, dir);
+
+            while here & opponent != 0 {
+                line[len] = here;
+                len += 1;
+                here = shift(here, dir);
+            }
+
+            if here & player != 0 {
+                line[..len].iter().for_each(|&bit| found(bit));
+            }
+        }
+    }
+
+    /// Returns what changes in the hash when each square in 'mask'
+    /// changes from 'old' to 'new'.
+    fn hash_change(mask: u64, old: Cell, new: Cell) -> u64 {
+        let mut remaining = mask;
+        let mut change = 0;
+
+        while remaining != 0 {
+            let index = remaining.trailing_zeros() as usize;
+            change ^= zobrist_key(index, old) ^ zobrist_key(index, new);
+            remaining &= remaining - 1;
+        }
+
+        change
+    }
+
+    /// Returns the masks for 'cell' and for its opponent.
+    fn sides(&self, cell: Cell) -> (u64, u64) {
+        match cell {
+            Cell::Black => (self.black, self.white),
+            Cell::White => (self.white, self.black),
+            Cell::Empty => (0, 0),
+        }
+    }
+
+    /// The bit that represents a location.
+    fn bit(loc: Loc) -> u64 {
+        1 << (loc.y * Bitboard::SIZE + loc.x)
+    }
+
+    /// The location represented by a mask containing a single bit.
+    fn loc(bit: u64) -> Loc {
+        let idx = bit.trailing_zeros() as usize;
+        Loc::new(idx % Bitboard::SIZE, idx / Bitboard::SIZE)
+    }
+}
+
+/// Moves every bit in 'bits' one square in the direction given,
+/// discarding any that fall off the board.
+fn shift(bits: u64, (amount, mask): (i32, u64)) -> u64 {
+    if amount > 0 {
+        (bits << amount) & mask
+    } else {
+        (bits >> -amount) & mask
+    }
+}
@@ -0,0 +1,441 @@
+use std::cell::OnceCell;
+use std::cmp::*;
+use std::error::Error;
+use std::fmt;
+use std::iter;
+use std::ops::*;
+use std::str::FromStr;
+
+use super::*;
+
+/// Holds the state of play; the board is essentially a two dimensional
+/// array of cells, but also caches some values used for scoring. The
+/// cell counts and Zobrist hash are kept up to date as cells change, but
+/// whether the game is over is worked out only when asked for.
+#[derive(Clone)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    cell_counts: [usize; 3],
+    game_over: OnceCell<bool>,
+    bits: Option<Bitboard>,
+    hash: u64,
+}
+
+impl Board {
+    /// The smallest width or height a board should have.
+    pub const MIN_SIZE: usize = 4;
+
+    /// The largest width or height a board should have; this
+    /// is as many columns as there are letters to name them.
+    pub const MAX_SIZE: usize = 26;
+
+    /// Creates a new board with the usual pattern of initial
+    /// cells- mostly empty.
+    pub fn new(width: usize, height: usize) -> Board {
+        use Cell::*;
+
+        let cells = vec![Empty; width * height];
+        let bits = if width == Bitboard::SIZE && height == Bitboard::SIZE {
+            Some(Bitboard::default())
+        } else {
+            None
+        };
+
+        let mut board = Board {
+            width,
+            height,
+            cells,
+            cell_counts: [width * height, 0, 0], // all Empty to start
+            game_over: OnceCell::new(),
+            bits,
+            hash: 0,
+        };
+
+        let center = Loc::new(width / 2, height / 2);
+        board.set_cell(Loc::new(center.x, center.y), White);
+        board.set_cell(Loc::new(center.x - 1, center.y - 1), White);
+        board.set_cell(Loc::new(center.x, center.y - 1), Black);
+        board.set_cell(Loc::new(center.x - 1, center.y), Black);
+
+        board
+    }
+
+    /// The width of the board.
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the board.
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// The center location in the board.
+    pub fn get_board_center(&self) -> Loc {
+        Loc::new(self.width / 2, self.height / 2)
+    }
+
+    /// This adds a delta to a location, and returns the new location so long as
+    /// it is in the board; if not it returns None.
+    pub fn offset_within(&self, loc: Loc, dx: isize, dy: isize) -> Option<Loc> {
+        loc.offset_within(dx, dy, self.width, self.height)
+    }
+
+    /// True if 'loc' is a location within the board.
+    pub fn contains(&self, loc: Loc) -> bool {
+        loc.x < self.width && loc.y < self.height
+    }
+
+    /// Returns an iterator over all the locations that are in the board.
+    pub fn locations(&self) -> impl Iterator<Item = Loc> + '_ {
+        let width = self.get_width();
+        let height = self.get_height();
+        (0..height).flat_map(move |y| (0..width).map(move |x| Loc::new(x, y)))
+    }
+
+    /// The board as a bitboard; this is available only for 8x8 boards, and
+    /// offers much faster ways to find moves than the Board methods.
+    pub fn get_bitboard(&self) -> Option<&Bitboard> {
+        self.bits.as_ref()
+    }
+
+    /// The Zobrist hash of the discs on the board; boards with the
+    /// same discs have the same hash, however they got there.
+    pub fn get_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// True if the game is over and no moves can be made.
+    pub fn is_game_over(&self) -> bool {
+        *self
+            .game_over
+            .get_or_init(|| !self.has_valid_move(Cell::White) && !self.has_valid_move(Cell::Black))
+    }
+
+    /// True if 'cell' has at least one valid move.
+    pub fn has_valid_move(&self, cell: Cell) -> bool {
+        if let Some(bits) = &self.bits {
+            bits.valid_moves(cell) != 0
+        } else {
+            self.locations()
+                .any(|loc| Movement::new(self, loc, cell).is_valid())
+        }
+    }
+
+    /// Counts the valid moves 'cell' has; this is quicker than
+    /// finding them.
+    pub fn count_valid_moves(&self, cell: Cell) -> usize {
+        if let Some(bits) = &self.bits {
+            bits.valid_moves(cell).count_ones() as usize
+        } else {
+            self.locations()
+                .filter(|&loc| Movement::new(self, loc, cell).is_valid())
+                .count()
+        }
+    }
+
+    /// This counts the number of board cells whose value is 'cell'.
+    pub fn count_cells(&self, cell: Cell) -> usize {
+        self.cell_counts[cell as usize]
+    }
+
+    /// Returns all valid locations where a given cell can be placed.
+    /// They are ordered so the one with the most flips is first; the
+    /// AI chooses this move.
+    pub fn find_valid_moves(&self, cell: Cell) -> Vec<Movement> {
+        let mut valid: Vec<_> = if let Some(bits) = &self.bits {
+            Bitboard::locations(bits.valid_moves(cell))
+                .map(|loc| Movement::new(self, loc, cell))
+                .collect()
+        } else {
+            let moves = self.locations().map(|loc| Movement::new(self, loc, cell));
+            moves.filter(|m| m.is_valid()).collect()
+        };
+
+        valid.sort_by(|left, right| left.get_score(self).cmp(&right.get_score(self)).reverse());
+        valid
+    }
+
+    /// Applies a cell change to the board and returns true if any
+    /// changes were made.
+    pub fn apply_change(&mut self, change: CellChange) -> bool {
+        let changed = self[change.loc] != change.cell;
+
+        if changed {
+            self.set_cell(change.loc, change.cell);
+        }
+
+        changed
+    }
+
+    /// Returns an iterator the gives the locations starting from 'start'
+    /// and incrementing by (dx, dy). The iterator ends when it runs off the board.
+    pub fn cells_from(&self, start: Loc, dx: isize, dy: isize) -> impl Iterator<Item = Loc> + '_ {
+        iter::successors(Some(start), move |&l| self.offset_within(l, dx, dy))
+    }
+
+    /// Stores a cell at the location given, keeping the bitboard, the
+    /// hash and the statistics we keep in step.
+    fn set_cell(&mut self, loc: Loc, cell: Cell) {
+        let idx = loc.y * self.width + loc.x;
+        let old = std::mem::replace(&mut self.cells[idx], cell);
+
+        self.cell_counts[old as usize] -= 1;
+        self.cell_counts[cell as usize] += 1;
+        self.hash ^= zobrist_key(idx, old) ^ zobrist_key(idx, cell);
+        self.game_over.take();
+
+        if let Some(bits) = &mut self.bits {
+            bits.set(loc, cell);
+        }
+    }
+}
+
+impl Index<Loc> for Board {
+    type Output = Cell;
+
+    fn index(&self, index: Loc) -> &Self::Output {
+        let idx = index.y * self.width + index.x;
+        &self.cells[idx]
+    }
+}
+
+/// Represents a position on the board. As text, a location is written as
+/// a column letter followed by a row number, counting from 'a1' at the top
+/// left; boards wider than 26 columns continue with 'aa', 'ab' and so on.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Loc {
+    /// The column, counting from 0 at the left.
+    pub x: usize,
+    /// The row, counting from 0 at the top.
+    pub y: usize,
+}
+
+impl Loc {
+    /// Creates a new Loc with the given co-ordinates.
+    pub fn new(x: usize, y: usize) -> Loc {
+        Loc { x, y }
+    }
+
+    /// This adds a delta to a location, and returns the new location so long as
+    /// it is in the indicated rage; if not it returns None. It can have an x
+    /// co-ordinate from 0 to width-1, and y can go from 0 to height-1.
+    pub fn offset_within(self, dx: isize, dy: isize, width: usize, height: usize) -> Option<Loc> {
+        if let Some(x) = add(self.x, dx) {
+            if let Some(y) = add(self.y, dy) {
+                if x < width && y < height {
+                    return Some(Loc::new(x, y));
+                }
+            }
+        }
+
+        return None;
+
+        fn add(left: usize, right: isize) -> Option<usize> {
+            match right.cmp(&0) {
+                Ordering::Greater => left.checked_add(right as usize),
+                Ordering::Less => left.checked_sub(right.unsigned_abs()),
+                Ordering::Equal => Some(left),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Loc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut letters = Vec::new();
+        let mut column = self.x + 1;
+
+        while column > 0 {
+            column -= 1;
+            letters.push(b'a' + (column % 26) as u8);
+            column /= 26;
+        }
+
+        letters.reverse();
+        let letters = String::from_utf8(letters).unwrap();
+        write!(f, "{}{}", letters, self.y + 1)
+    }
+}
+
+impl FromStr for Loc {
+    type Err = ParseLocError;
+
+    /// Parses a location like 'f5'; the column letters may be in either case.
+    fn from_str(text: &str) -> Result<Loc, ParseLocError> {
+        let error = || ParseLocError(text.to_string());
+        let digits_at = text
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .ok_or_else(error)?;
+        let (letters, digits) = text.split_at(digits_at);
+
+        if letters.is_empty() || digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(error());
+        }
+
+        let mut column = 0usize;
+        for b in letters.bytes() {
+            let letter = (b.to_ascii_lowercase() - b'a') as usize;
+            column = column
+                .checked_mul(26)
+                .and_then(|c| c.checked_add(letter + 1))
+                .ok_or_else(error)?;
+        }
+
+        let row: usize = digits.parse().map_err(|_| error())?;
+        if row == 0 {
+            return Err(error());
+        }
+
+        Ok(Loc::new(column - 1, row - 1))
+    }
+}
+
+/// The error returned when text cannot be parsed as a Loc;
+/// it holds the text that was rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseLocError(pub String);
+
+impl fmt::Display for ParseLocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a board location", self.0)
+    }
+}
+
+impl Error for ParseLocError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn offset_within_moves_inside_the_range() {
+        let loc = Loc::new(3, 3);
+        assert_eq!(loc.offset_within(1, -1, 8, 8), Some(Loc::new(4, 2)));
+        assert_eq!(loc.offset_within(-3, 4, 8, 8), Some(Loc::new(0, 7)));
+        assert_eq!(loc.offset_within(0, 0, 8, 8), Some(loc));
+    }
+
+    #[test]
+    fn offset_within_stops_at_each_edge() {
+        assert_eq!(Loc::new(0, 4).offset_within(-1, 0, 8, 8), None);
+        assert_eq!(Loc::new(4, 0).offset_within(0, -1, 8, 8), None);
+        assert_eq!(Loc::new(7, 4).offset_within(1, 0, 8, 8), None);
+        assert_eq!(Loc::new(4, 7).offset_within(0, 1, 8, 8), None);
+        assert_eq!(Loc::new(0, 0).offset_within(-1, -1, 8, 8), None);
+        assert_eq!(Loc::new(7, 7).offset_within(1, 1, 8, 8), None);
+    }
+
+    #[test]
+    fn offset_within_rejects_locations_already_outside() {
+        assert_eq!(Loc::new(8, 0).offset_within(0, 0, 8, 8), None);
+        assert_eq!(Loc::new(9, 0).offset_within(-1, 0, 8, 8), None);
+        assert_eq!(Loc::new(0, 0).offset_within(0, 0, 0, 0), None);
+    }
+
+    #[test]
+    fn offset_within_survives_extreme_offsets() {
+        let loc = Loc::new(3, 3);
+        assert_eq!(loc.offset_within(isize::MAX, 0, 8, 8), None);
+        assert_eq!(loc.offset_within(isize::MIN, 0, 8, 8), None);
+        assert_eq!(loc.offset_within(0, isize::MIN, 8, 8), None);
+        assert_eq!(
+            Loc::new(usize::MAX, 0).offset_within(1, 0, usize::MAX, 8),
+            None
+        );
+    }
+
+    #[test]
+    fn board_offset_within_uses_the_board_size() {
+        let board = Board::new(10, 6);
+        assert_eq!(
+            board.offset_within(Loc::new(8, 4), 1, 1),
+            Some(Loc::new(9, 5))
+        );
+        assert_eq!(board.offset_within(Loc::new(9, 5), 0, 1), None);
+        assert_eq!(board.offset_within(Loc::new(9, 5), 1, 0), None);
+    }
+
+    #[test]
+    fn boards_past_the_largest_size_still_play() {
+        let mut board = Board::new(30, 30);
+        let mut cell = Cell::Black;
+
+        for _ in 0..40 {
+            if let Some(mv) = board.find_valid_moves(cell).pop() {
+                mv.play(&mut board);
+            }
+            cell = cell.flipped();
+        }
+
+        // The hash kept up to date matches one worked out from scratch.
+        let mut copy = Board::new(30, 30);
+        for loc in board.locations() {
+            copy.apply_change(CellChange::new(board[loc], loc));
+        }
+        assert_eq!(copy.get_hash(), board.get_hash());
+        assert!(board.count_cells(Cell::Empty) < 900 - 40);
+    }
+
+    /// Works out the cell counts and whether the game is over from
+    /// scratch, as the board did after every flip before it kept them
+    /// up to date.
+    fn recount(board: &Board) -> ([usize; 3], bool) {
+        let mut counts = [0; 3];
+        for loc in board.locations() {
+            counts[board[loc] as usize] += 1;
+        }
+
+        let game_over = board.find_valid_moves(Cell::White).is_empty()
+            && board.find_valid_moves(Cell::Black).is_empty();
+        (counts, game_over)
+    }
+
+    /// Plays random games flip by flip, as the user interface animates
+    /// them, reading the counts and whether the game is over after each
+    /// flip; 'read' gets them. Returns how long this took.
+    fn time_flip_by_flip(width: usize, games: u64, read: impl Fn(&Board) -> usize) -> Duration {
+        let start = Instant::now();
+        let mut total = 0;
+
+        for seed in 0..games {
+            let mut game = RandomGame::new(width, width, seed);
+            while !game.is_over() {
+                game.play_turn_watching(|board| total += read(board));
+            }
+        }
+
+        assert!(total > 0);
+        start.elapsed()
+    }
+
+    /// Compares keeping the board statistics up to date with recounting
+    /// them after every flip. This is a benchmark rather than a test, so
+    /// it runs only when asked for, like this:
+    ///
+    /// cargo test --release -p rusthello-core flip_by_flip -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn flip_by_flip_statistics_benchmark() {
+        for width in [8, 12] {
+            let kept = time_flip_by_flip(width, 200, |board| {
+                board.count_cells(Cell::Black) + board.is_game_over() as usize
+            });
+            let recounted = time_flip_by_flip(width, 200, |board| {
+                let (counts, game_over) = recount(board);
+                counts[Cell::Black as usize] + game_over as usize
+            });
+
+            println!(
+                "{}x{}, 200 games: kept up to date {:?}, recounted {:?}",
+                width, width, kept, recounted
+            );
+            assert!(kept < recounted);
+        }
+    }
+}
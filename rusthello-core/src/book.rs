@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+use super::*;
+
+/// The first bytes of every book file; the last is the
+/// version of the format.
+const MAGIC: &[u8; 8] = b"RHBOOK\0\x01";
+
+/// How many moves into each game the book records.
+const BOOK_MOVES: usize = 20;
+
+/// The number of ways a square board can be turned or flipped
+/// and still look like the same position.
+const SYMMETRIES: usize = 8;
+
+/// An opening book: the moves that were played in a collection of games
+/// from each position near the start, with how often each was played. It
+/// applies only to 8x8 boards.
+///
+/// Positions that differ only by turning or flipping the board are the
+/// same position to the book, so it stores just one of them; this is the
+/// 'normalized' position, and moves are stored as they would be played in
+/// it.
+///
+/// On disk, a book is the magic bytes, then the number of positions, then
+/// each position: its key, the number of moves, and each move's square
+/// ('y * 8 + x') and weight. Numbers are little-endian; keys are 64 bits,
+/// counts 32 bits (positions) or 8 bits (moves), and weights 16 bits.
+#[derive(Default)]
+pub struct OpeningBook {
+    positions: HashMap<u64, Vec<BookMove>>,
+}
+
+/// A move in the book, and how many of the games played it.
+#[derive(Copy, Clone)]
+struct BookMove {
+    loc: Loc,
+    weight: u16,
+}
+
+impl OpeningBook {
+    /// Creates an empty book.
+    pub fn new() -> OpeningBook {
+        OpeningBook::default()
+    }
+
+    /// The number of positions the book has moves for.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// True if the book has no moves at all.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Adds the first moves of a game to the book. This fails if a move
+    /// in the transcript is not valid, in which case the book is unchanged.
+    pub fn add_game(&mut self, transcript: &Transcript) -> Result<(), TranscriptError> {
+        let board = Board::new(Bitboard::SIZE, Bitboard::SIZE);
+        let game = Game::from_transcript(board, transcript)?;
+
+        let moves = game
+            .history
+            .iter()
+            .filter_map(|snapshot| match snapshot.play {
+                Play::Move(loc) => Some((snapshot, loc)),
+                Play::Pass => None,
+            });
+
+        for (snapshot, loc) in moves.take(BOOK_MOVES) {
+            if let Some(bits) = snapshot.board.get_bitboard() {
+                let (key, symmetry) = normalize(bits, snapshot.next_move);
+                self.add_move(key, transform(loc, symmetry));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Chooses a move from the book for 'cell' on the board given, at
+    /// random but favoring the moves played most often. This returns
+    /// None if the book has no move for this position.
+    pub fn choose_move(&self, board: &Board, cell: Cell) -> Option<Movement> {
+        let bits = board.get_bitboard()?;
+        let (key, symmetry) = normalize(bits, cell);
+        let moves = self.positions.get(&key)?;
+
+        let total: usize = moves.iter().map(|mv| mv.weight as usize).sum();
+        let mut choice = Random::from_time().below(total);
+        let chosen = moves.iter().find(|mv| {
+            let found = choice < mv.weight as usize;
+            choice = choice.saturating_sub(mv.weight as usize);
+            found
+        })?;
+
+        let movement = Movement::new(board, untransform(chosen.loc, symmetry), cell);
+        Some(movement).filter(|mv| mv.is_valid())
+    }
+
+    /// Writes the book in its binary format.
+    pub fn save(&self, out: &mut dyn Write) -> io::Result<()> {
+        let mut keys: Vec<&u64> = self.positions.keys().collect();
+        keys.sort();
+
+        out.write_all(MAGIC)?;
+        out.write_all(&(keys.len() as u32).to_le_bytes())?;
+
+        for key in keys {
+            let moves = &self.positions[key];
+            out.write_all(&key.to_le_bytes())?;
+            out.write_all(&[moves.len() as u8])?;
+
+            for mv in moves {
+                let square = (mv.loc.y * Bitboard::SIZE + mv.loc.x) as u8;
+                out.write_all(&[square])?;
+                out.write_all(&mv.weight.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a book written by save().
+    pub fn load(input: &mut dyn Read) -> io::Result<OpeningBook> {
+        if read_bytes::<8>(input)? != *MAGIC {
+            return Err(invalid("this is not an opening book"));
+        }
+
+        let mut book = OpeningBook::new();
+        let count = u32::from_le_bytes(read_bytes(input)?);
+
+        for _ in 0..count {
+            let key = u64::from_le_bytes(read_bytes(input)?);
+            let [move_count] = read_bytes(input)?;
+            let mut moves = Vec::with_capacity(move_count as usize);
+
+            for _ in 0..move_count {
+                let [square] = read_bytes(input)?;
+                let weight = u16::from_le_bytes(read_bytes(input)?);
+                let square = square as usize;
+
+                if square >= Bitboard::SIZE * Bitboard::SIZE || weight == 0 {
+                    return Err(invalid("the opening book contains an invalid move"));
+                }
+
+                let loc = Loc::new(square % Bitboard::SIZE, square / Bitboard::SIZE);
+                moves.push(BookMove { loc, weight });
+            }
+
+            book.positions.insert(key, moves);
+        }
+
+        Ok(book)
+    }
+
+    /// Counts one more game playing 'loc' in the normalized
+    /// position whose key is given.
+    fn add_move(&mut self, key: u64, loc: Loc) {
+        let moves = self.positions.entry(key).or_default();
+
+        if let Some(mv) = moves.iter_mut().find(|mv| mv.loc == loc) {
+            mv.weight = mv.weight.saturating_add(1);
+        } else if moves.len() < u8::MAX as usize {
+            // We can only record 255 moves per position, but
+            // no position has that many.
+            moves.push(BookMove { loc, weight: 1 });
+        }
+    }
+}
+
+/// Finds the normalized form of a position with 'cell' to move; this
+/// is whichever of the ways of turning or flipping the board gives the
+/// smallest key. This returns that key, and the symmetry that gives it.
+fn normalize(bits: &Bitboard, cell: Cell) -> (u64, usize) {
+    (0..SYMMETRIES)
+        .map(|symmetry| {
+            let mut turned = Bitboard::default();
+            for side in [Cell::Black, Cell::White] {
+                for loc in Bitboard::locations(bits.get_mask(side)) {
+                    turned.set(transform(loc, symmetry), side);
+                }
+            }
+            (position_key(turned.get_hash(), cell), symmetry)
+        })
+        .min()
+        .unwrap()
+}
+
+/// Moves a location as the board is turned or flipped. Each bit of
+/// 'symmetry' selects a step: mirroring left to right, then top to
+/// bottom, then swapping rows for columns.
+fn transform(loc: Loc, symmetry: usize) -> Loc {
+    const LAST: usize = Bitboard::SIZE - 1;
+    let mut loc = loc;
+
+    if symmetry & 1 != 0 {
+        loc.x = LAST - loc.x;
+    }
+    if symmetry & 2 != 0 {
+        loc.y = LAST - loc.y;
+    }
+    if symmetry & 4 != 0 {
+        loc = Loc::new(loc.y, loc.x);
+    }
+
+    loc
+}
+
+/// Undoes transform(), taking its steps in the opposite order.
+fn untransform(loc: Loc, symmetry: usize) -> Loc {
+    const LAST: usize = Bitboard::SIZE - 1;
+    let mut loc = loc;
+
+    if symmetry & 4 != 0 {
+        loc = Loc::new(loc.y, loc.x);
+    }
+    if symmetry & 2 != 0 {
+        loc.y = LAST - loc.y;
+    }
+    if symmetry & 1 != 0 {
+        loc.x = LAST - loc.x;
+    }
+
+    loc
+}
+
+/// Reads exactly 'N' bytes; this fails at the end of the file.
+fn read_bytes<const N: usize>(input: &mut dyn Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    input.read_exact(&mut bytes).map_err(|err| {
+        if err.kind() == ErrorKind::UnexpectedEof {
+            Error::new(ErrorKind::UnexpectedEof, "the opening book is incomplete")
+        } else {
+            err
+        }
+    })?;
+    Ok(bytes)
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAMES: [&str; 3] = [
+        "f5d6c3d3c4f4f6f3e6e7",
+        "d3e3f2c4b3g1f4g5g4c5",
+        "c4c3c2c5c6e3f6b7e2f3",
+    ];
+
+    fn book_of(games: &[&str]) -> OpeningBook {
+        let mut book = OpeningBook::new();
+        for game in games {
+            book.add_game(&game.parse().unwrap()).unwrap();
+        }
+        book
+    }
+
+    /// Plays the first 'moves' moves of a game, and returns the board.
+    fn board_after(game: &str, moves: usize) -> Board {
+        let transcript: Transcript = game.parse().unwrap();
+        let mut partial = Transcript::new();
+        for &play in &transcript.get_moves()[..moves] {
+            partial.push(play);
+        }
+        Game::from_transcript(Board::new(8, 8), &partial)
+            .unwrap()
+            .to_board()
+            .clone()
+    }
+
+    /// Returns the board turned or flipped by 'symmetry'.
+    fn transformed(board: &Board, symmetry: usize) -> Board {
+        let mut turned = Board::new(8, 8);
+        for loc in board.locations() {
+            turned.apply_change(CellChange::new(board[loc], transform(loc, symmetry)));
+        }
+        turned
+    }
+
+    #[test]
+    fn transform_is_undone_by_untransform() {
+        for symmetry in 0..SYMMETRIES {
+            for loc in Board::new(8, 8).locations() {
+                assert_eq!(untransform(transform(loc, symmetry), symmetry), loc);
+            }
+        }
+    }
+
+    #[test]
+    fn moves_are_found_in_every_symmetric_position() {
+        let game = GAMES[0];
+        let book = book_of(&[game]);
+        let transcript: Transcript = game.parse().unwrap();
+
+        for moves in [3, 6] {
+            let board = board_after(game, moves);
+            let expected = match transcript.get_moves()[moves] {
+                Play::Move(loc) => loc,
+                Play::Pass => unreachable!(),
+            };
+
+            // There are no passes this early, so black moves
+            // after an even number of moves.
+            let cell = if moves % 2 == 0 {
+                Cell::Black
+            } else {
+                Cell::White
+            };
+
+            for symmetry in 0..SYMMETRIES {
+                let turned = transformed(&board, symmetry);
+                let mv = book.choose_move(&turned, cell);
+                assert_eq!(
+                    mv.and_then(|mv| mv.get_loc()),
+                    Some(transform(expected, symmetry)),
+                    "after {} moves, symmetry {}",
+                    moves,
+                    symmetry
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn saved_books_load_unchanged() {
+        let book = book_of(&GAMES);
+        let mut saved = Vec::new();
+        book.save(&mut saved).unwrap();
+
+        let loaded = OpeningBook::load(&mut saved.as_slice()).unwrap();
+        assert_eq!(loaded.len(), book.len());
+
+        for (key, moves) in &book.positions {
+            let found = &loaded.positions[key];
+            let as_pairs = |moves: &[BookMove]| -> Vec<(Loc, u16)> {
+                moves.iter().map(|mv| (mv.loc, mv.weight)).collect()
+            };
+            assert_eq!(as_pairs(found), as_pairs(moves));
+        }
+
+        let mut saved_again = Vec::new();
+        loaded.save(&mut saved_again).unwrap();
+        assert_eq!(saved_again, saved);
+    }
+
+    #[test]
+    fn damaged_books_do_not_load() {
+        let mut saved = Vec::new();
+        book_of(&GAMES).save(&mut saved).unwrap();
+
+        let truncated = &saved[..saved.len() - 1];
+        let err = OpeningBook::load(&mut &truncated[..]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let mut wrong_magic = saved.clone();
+        wrong_magic[0] = b'X';
+        let err = OpeningBook::load(&mut wrong_magic.as_slice())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn positions_not_in_the_book_have_no_move() {
+        let book = book_of(&GAMES);
+
+        // No game in the book reaches this position.
+        let board = board_after("e6f6f5d6f7g4", 6);
+        assert!(book.choose_move(&board, Cell::Black).is_none());
+
+        // The book knows the start, but not with white to move.
+        assert!(book.choose_move(&Board::new(8, 8), Cell::Black).is_some());
+        assert!(book.choose_move(&Board::new(8, 8), Cell::White).is_none());
+
+        assert!(OpeningBook::new()
+            .choose_move(&Board::new(8, 8), Cell::Black)
+            .is_none());
+        assert!(book.choose_move(&Board::new(10, 10), Cell::Black).is_none());
+    }
+}
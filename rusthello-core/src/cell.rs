@@ -1,10 +1,13 @@
 use std::fmt;
 
 /// Lists the states a cell on the board can be in.
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Cell {
+    /// No disc.
     Empty,
+    /// A white disc.
     White,
+    /// A black disc.
     Black,
 }
 
@@ -26,6 +29,16 @@ impl fmt::Display for Cell {
 }
 
 impl Cell {
+    /// Returns the name of the player who plays this cell.
+    pub fn name(self) -> &'static str {
+        match self {
+            Cell::Empty => "Nobody",
+            Cell::White => "White",
+            Cell::Black => "Black",
+        }
+    }
+
+    /// Returns the other player's cell; Empty stays Empty.
     pub fn flipped(self) -> Cell {
         match self {
             Cell::Black => Cell::White,
@@ -0,0 +1,416 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::*;
+
+/// With this many empty cells or fewer, the bitboard solver
+/// tries moves in whatever order it finds them.
+const UNORDERED_EMPTIES: u32 = 6;
+
+/// How a game ends, from one player's point of view.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The player ends with more discs than their opponent.
+    Win,
+    /// The player ends with fewer discs than their opponent.
+    Loss,
+    /// Both players end with the same number of discs.
+    Draw,
+}
+
+/// The exact result of an endgame, when both players play perfectly.
+#[derive(Clone)]
+pub struct Solution {
+    /// The final number of discs the player to move will have,
+    /// less the number their opponent will have.
+    pub score: i32,
+    /// The move that achieves this score; this is invalid if the
+    /// player must pass, or if the game is already over.
+    pub best_move: Movement,
+}
+
+impl Solution {
+    /// Whether the player to move wins, loses or draws.
+    pub fn get_outcome(&self) -> Outcome {
+        match self.score.cmp(&0) {
+            Ordering::Greater => Outcome::Win,
+            Ordering::Less => Outcome::Loss,
+            Ordering::Equal => Outcome::Draw,
+        }
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.get_outcome() {
+            Outcome::Win => write!(f, "win by {}", self.score)?,
+            Outcome::Loss => write!(f, "loss by {}", -self.score)?,
+            Outcome::Draw => write!(f, "draw")?,
+        }
+
+        match self.best_move.get_loc() {
+            Some(loc) => write!(f, ", playing {}", loc),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Board {
+    /// Finds the exact outcome of the game for 'cell', who is to move, by
+    /// searching every line of play to the end. This takes time that grows
+    /// very quickly with the number of empty cells, so it is practical
+    /// only near the end of the game.
+    pub fn solve_endgame(&self, cell: Cell) -> Solution {
+        self.solve_endgame_with(cell, &mut TranspositionTable::default())
+    }
+
+    /// Works like solve_endgame(), but keeps what it learns in 'table'.
+    pub fn solve_endgame_with(&self, cell: Cell, table: &mut TranspositionTable) -> Solution {
+        let mut state = SearchState {
+            table,
+            deadline: None,
+            cancel: None,
+        };
+
+        self.solve_endgame_within(cell, &mut state)
+            .expect("a solve with no limits always finishes")
+    }
+
+    /// Works like solve_endgame(), but uses the table in 'state', and
+    /// stops when the state says it must; this then returns None.
+    pub(crate) fn solve_endgame_within(
+        &self,
+        cell: Cell,
+        state: &mut SearchState,
+    ) -> Option<Solution> {
+        let limit = (self.get_width() * self.get_height()) as i32 + 1;
+        let mut best_move = Movement::default();
+        let mut alpha = -limit;
+
+        for (mv, next) in find_successors(self, cell) {
+            let score = -solve_position(state, &next, cell.flipped(), -limit, -alpha)?;
+            if score > alpha || !best_move.is_valid() {
+                alpha = score;
+                best_move = mv;
+            }
+        }
+
+        if best_move.is_valid() {
+            Some(Solution {
+                score: alpha,
+                best_move,
+            })
+        } else {
+            Some(Solution {
+                score: solve_position(state, self, cell, -limit, limit)?,
+                best_move,
+            })
+        }
+    }
+}
+
+/// Returns the final disc difference for 'cell', who is to move, within
+/// the bounds alpha and beta. This uses the bitboard when the board has
+/// one, as it's very much faster. This returns None if the search must
+/// stop before it is done.
+fn solve_position(
+    state: &mut SearchState,
+    board: &Board,
+    cell: Cell,
+    alpha: i32,
+    beta: i32,
+) -> Option<i32> {
+    match board.get_bitboard() {
+        Some(bits) => solve_bits(state, *bits, cell, alpha, beta, false),
+        None => solve(state, board, cell, alpha, beta, false),
+    }
+}
+
+/// Returns the final disc difference for 'cell', who is to move, within
+/// the bounds alpha and beta. 'passed' is true if the opponent has just
+/// passed, so that if 'cell' must pass too, the game is over. This
+/// returns None if the search must stop.
+fn solve(
+    state: &mut SearchState,
+    board: &Board,
+    cell: Cell,
+    mut alpha: i32,
+    beta: i32,
+    passed: bool,
+) -> Option<i32> {
+    if state.must_stop() {
+        return None;
+    }
+
+    let mut successors = find_successors(board, cell);
+
+    if successors.is_empty() {
+        return if passed {
+            Some(board.count_cells(cell) as i32 - board.count_cells(cell.flipped()) as i32)
+        } else {
+            Some(-solve(state, board, cell.flipped(), -beta, -alpha, true)?)
+        };
+    }
+
+    let hash = board.get_hash();
+    if let Some(entry) = state.table.probe(hash, cell) {
+        if let Some(score) = get_solved_cutoff(entry, alpha, beta) {
+            return Some(score);
+        }
+        if let Some(first) = successors
+            .iter()
+            .position(|(mv, _)| mv.get_loc() == entry.best_move)
+        {
+            successors[..=first].rotate_right(1);
+        }
+    }
+
+    let original_alpha = alpha;
+    let mut best_score = i32::MIN;
+    let mut best_move = None;
+
+    for (mv, next) in successors {
+        let score = -solve(state, &next, cell.flipped(), -beta, -alpha, false)?;
+        if score > best_score {
+            best_score = score;
+            best_move = mv.get_loc();
+        }
+        if score >= beta {
+            break;
+        }
+        alpha = alpha.max(score);
+    }
+
+    let entry = Entry {
+        depth: Entry::SOLVED,
+        bound: Bound::for_score(best_score, original_alpha, beta),
+        score: score_for_difference(best_score),
+        best_move,
+    };
+    state.table.store(hash, cell, entry);
+
+    Some(best_score)
+}
+
+/// Returns the valid moves for 'cell', each with the board that results,
+/// ordered so that the moves that leave the opponent the fewest replies
+/// come first; these tend to be the best, which lets alpha-beta pruning
+/// cut off more of the search.
+fn find_successors(board: &Board, cell: Cell) -> Vec<(Movement, Board)> {
+    let mut successors: Vec<(Movement, Board)> = board
+        .find_valid_moves(cell)
+        .into_iter()
+        .map(|mv| {
+            let mut next = board.clone();
+            mv.play(&mut next);
+            (mv, next)
+        })
+        .collect();
+
+    successors.sort_by_cached_key(|(_, next)| next.count_valid_moves(cell.flipped()));
+    successors
+}
+
+/// Works like solve(), but on a bitboard. So as not to slow the search
+/// down, this checks whether it must stop only where it uses the table.
+fn solve_bits(
+    state: &mut SearchState,
+    bits: Bitboard,
+    cell: Cell,
+    mut alpha: i32,
+    beta: i32,
+    passed: bool,
+) -> Option<i32> {
+    let moves = bits.valid_moves(cell);
+
+    if moves == 0 {
+        return if passed {
+            Some(
+                bits.get_mask(cell).count_ones() as i32
+                    - bits.get_mask(cell.flipped()).count_ones() as i32,
+            )
+        } else {
+            Some(-solve_bits(
+                state,
+                bits,
+                cell.flipped(),
+                -beta,
+                -alpha,
+                true,
+            )?)
+        };
+    }
+
+    let mut successors: Vec<(Loc, Bitboard)> = Bitboard::locations(moves)
+        .map(|loc| (loc, bits.play(cell, loc)))
+        .collect();
+
+    // Near the end, ordering the moves and using the table
+    // costs more than it saves.
+    let empties = bits.get_mask(Cell::Empty).count_ones();
+    if empties <= UNORDERED_EMPTIES {
+        for (_, next) in successors {
+            let score = -solve_bits(state, next, cell.flipped(), -beta, -alpha, false)?;
+            if score >= beta {
+                return Some(score);
+            }
+            alpha = alpha.max(score);
+        }
+
+        return Some(alpha);
+    }
+
+    if state.must_stop() {
+        return None;
+    }
+
+    successors.sort_by_cached_key(|(_, next)| next.valid_moves(cell.flipped()).count_ones());
+
+    let hash = bits.get_hash();
+    if let Some(entry) = state.table.probe(hash, cell) {
+        if let Some(score) = get_solved_cutoff(entry, alpha, beta) {
+            return Some(score);
+        }
+        if let Some(first) = successors
+            .iter()
+            .position(|&(loc, _)| Some(loc) == entry.best_move)
+        {
+            successors[..=first].rotate_right(1);
+        }
+    }
+
+    let original_alpha = alpha;
+    let mut best_score = i32::MIN;
+    let mut best_move = None;
+
+    for (loc, next) in successors {
+        let score = -solve_bits(state, next, cell.flipped(), -beta, -alpha, false)?;
+        if score > best_score {
+            best_score = score;
+            best_move = Some(loc);
+        }
+        if score >= beta {
+            break;
+        }
+        alpha = alpha.max(score);
+    }
+
+    let entry = Entry {
+        depth: Entry::SOLVED,
+        bound: Bound::for_score(best_score, original_alpha, beta),
+        score: score_for_difference(best_score),
+        best_move,
+    };
+    state.table.store(hash, cell, entry);
+
+    Some(best_score)
+}
+
+/// Returns the disc difference stored in 'entry', if it settles the
+/// position for the window given; entries the search stored, which
+/// did not reach the end of the game, never do.
+fn get_solved_cutoff(entry: &Entry, alpha: i32, beta: i32) -> Option<i32> {
+    let solved = Entry {
+        score: difference_for_score(entry.score),
+        ..*entry
+    };
+
+    solved.get_cutoff(Entry::SOLVED, alpha, beta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    /// Plays random moves on a new board until it has 'empties' empty
+    /// cells, and returns the board and the player to move. The game
+    /// may end first, so the board may have more empty cells.
+    fn random_position(width: usize, height: usize, empties: usize, seed: u64) -> (Board, Cell) {
+        let mut game = RandomGame::new(width, height, seed);
+        game.play_until(empties);
+        (game.get_board().clone(), game.get_cell())
+    }
+
+    /// The final disc difference for 'cell', found by trying every
+    /// line of play without any pruning.
+    fn minimax(board: &Board, cell: Cell) -> i32 {
+        if board.is_game_over() {
+            return board.count_cells(cell) as i32 - board.count_cells(cell.flipped()) as i32;
+        }
+
+        let moves = board.find_valid_moves(cell);
+        if moves.is_empty() {
+            return -minimax(board, cell.flipped());
+        }
+
+        moves
+            .iter()
+            .map(|mv| {
+                let mut next = board.clone();
+                mv.play(&mut next);
+                -minimax(&next, cell.flipped())
+            })
+            .max()
+            .unwrap()
+    }
+
+    /// Checks the solution for a position against minimax, both for
+    /// its score and for the score its best move actually leads to.
+    fn check_solution(board: &Board, cell: Cell) {
+        let solution = board.solve_endgame(cell);
+        assert_eq!(solution.score, minimax(board, cell), "score");
+
+        if solution.best_move.is_valid() {
+            let mut next = board.clone();
+            solution.best_move.play(&mut next);
+            assert_eq!(-minimax(&next, cell.flipped()), solution.score, "best move");
+        } else {
+            assert!(!board.has_valid_move(cell));
+        }
+    }
+
+    #[test]
+    fn bitboard_solutions_match_minimax() {
+        for seed in 0..6 {
+            let (board, cell) = random_position(8, 8, 8 + seed as usize % 2, seed);
+            check_solution(&board, cell);
+        }
+    }
+
+    #[test]
+    fn general_solutions_match_minimax() {
+        for seed in 0..6 {
+            let (board, cell) = random_position(6, 6, 8 + seed as usize % 2, seed);
+            check_solution(&board, cell);
+        }
+    }
+
+    #[test]
+    fn solutions_match_minimax_when_a_player_must_pass() {
+        // We look for positions where the player to move must pass
+        // at once, on each kind of board.
+        for (width, height) in [(8, 8), (6, 6)] {
+            let found = (0..)
+                .map(|seed| random_position(width, height, 9, seed))
+                .find(|(board, cell)| !board.is_game_over() && !board.has_valid_move(*cell))
+                .unwrap();
+
+            let (board, cell) = found;
+            check_solution(&board, cell);
+            assert!(!board.solve_endgame(cell).best_move.is_valid());
+        }
+    }
+
+    #[test]
+    fn finished_games_score_the_disc_difference() {
+        let transcript: Transcript = "d3c3b3d2e1d6d7e3f4".parse().unwrap();
+        let game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        let board = game.to_board();
+        let solution = board.solve_endgame(Cell::White);
+
+        assert_eq!(solution.score, -(board.count_cells(Cell::Black) as i32));
+        assert_eq!(solution.get_outcome(), Outcome::Loss);
+        assert!(!solution.best_move.is_valid());
+    }
+}
@@ -0,0 +1,330 @@
+use super::*;
+
+/// The pairs of opposite directions that make up the four lines
+/// through a cell: horizontal, vertical and the two diagonals.
+const AXES: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// All eight directions around a cell.
+const NEIGHBORS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// How much each feature of a position is worth. Each feature is measured
+/// as the player's count less the opponent's, so negative weights mark
+/// features that are bad to have.
+#[derive(Copy, Clone)]
+struct Weights {
+    discs: i32,
+    mobility: i32,
+    potential_mobility: i32,
+    frontier: i32,
+    stable: i32,
+    corners: i32,
+    x_squares: i32,
+    c_squares: i32,
+    parity: i32,
+}
+
+/// The weights at the start of the game: having few discs and many
+/// moves matters most, and the squares next to corners are dangerous.
+const OPENING: Weights = Weights {
+    discs: -2,
+    mobility: 20,
+    potential_mobility: 8,
+    frontier: -8,
+    stable: 20,
+    corners: 150,
+    x_squares: -60,
+    c_squares: -25,
+    parity: 0,
+};
+
+/// The weights when the board is full: by then discs count, and
+/// so does getting the last move.
+const ENDGAME: Weights = Weights {
+    discs: 10,
+    mobility: 10,
+    potential_mobility: 2,
+    frontier: -2,
+    stable: 30,
+    corners: 80,
+    x_squares: -10,
+    c_squares: -5,
+    parity: 30,
+};
+
+impl Weights {
+    /// Blends the opening and endgame weights according to how
+    /// far the game has gone, from 0 (empty) to 'full'.
+    fn for_phase(phase: i32, full: i32) -> Weights {
+        let blend =
+            |opening: i32, endgame: i32| (opening * (full - phase) + endgame * phase) / full;
+
+        Weights {
+            discs: blend(OPENING.discs, ENDGAME.discs),
+            mobility: blend(OPENING.mobility, ENDGAME.mobility),
+            potential_mobility: blend(OPENING.potential_mobility, ENDGAME.potential_mobility),
+            frontier: blend(OPENING.frontier, ENDGAME.frontier),
+            stable: blend(OPENING.stable, ENDGAME.stable),
+            corners: blend(OPENING.corners, ENDGAME.corners),
+            x_squares: blend(OPENING.x_squares, ENDGAME.x_squares),
+            c_squares: blend(OPENING.c_squares, ENDGAME.c_squares),
+            parity: blend(OPENING.parity, ENDGAME.parity),
+        }
+    }
+}
+
+impl Board {
+    /// Estimates how good the board is for 'cell', assuming it is
+    /// 'cell's turn to move; higher is better. This combines mobility,
+    /// potential mobility, frontier discs, stable discs, the corners and
+    /// the squares next to them, and parity, weighted according to how
+    /// full the board is.
+    ///
+    /// The opponent's score is the negation of this, except for parity:
+    /// that counts for whichever player is assumed to move, so with an
+    /// odd number of empty cells it counts for both.
+    pub fn evaluate(&self, cell: Cell) -> i32 {
+        let opponent = cell.flipped();
+        let total = (self.get_width() * self.get_height()) as i32;
+        let empty = self.count_cells(Cell::Empty) as i32;
+        let weights = Weights::for_phase(total - empty, total);
+
+        let discs = self.count_cells(cell) as i32 - self.count_cells(opponent) as i32;
+        let mobility =
+            self.count_valid_moves(cell) as i32 - self.count_valid_moves(opponent) as i32;
+
+        let mut potential_mobility = 0;
+        let mut frontier = 0;
+
+        for loc in self.locations() {
+            let here = self[loc];
+            let next_to = |target: Cell| {
+                NEIGHBORS.iter().any(|&(dx, dy)| {
+                    self.offset_within(loc, dx, dy)
+                        .is_some_and(|l| self[l] == target)
+                })
+            };
+
+            if here == Cell::Empty {
+                // An empty cell next to the opponent's discs is a move
+                // we might get later; and vice versa.
+                if next_to(opponent) {
+                    potential_mobility += 1;
+                }
+                if next_to(cell) {
+                    potential_mobility -= 1;
+                }
+            } else if next_to(Cell::Empty) {
+                frontier += if here == cell { 1 } else { -1 };
+            }
+        }
+
+        let stable = self.count_stable_discs(cell) - self.count_stable_discs(opponent);
+        let (corners, x_squares, c_squares) = self.measure_corners(cell);
+
+        // With an odd number of empty cells, the player to move
+        // should get the last move. Unlike the other terms, this
+        // is not the negation of the opponent's.
+        let parity = empty % 2;
+
+        weights.discs * discs
+            + weights.mobility * mobility
+            + weights.potential_mobility * potential_mobility
+            + weights.frontier * frontier
+            + weights.stable * stable
+            + weights.corners * corners
+            + weights.x_squares * x_squares
+            + weights.c_squares * c_squares
+            + weights.parity * parity
+    }
+
+    /// Estimates how good the board is for 'cell', like evaluate(), but
+    /// much more crudely: this counts discs, but gives extra weight to
+    /// corners and to having more moves available than the opponent.
+    pub fn evaluate_simply(&self, cell: Cell) -> i32 {
+        let opponent = cell.flipped();
+        let (corners, _, _) = self.measure_corners(cell);
+        let discs = self.count_cells(cell) as i32 - self.count_cells(opponent) as i32;
+        let mobility =
+            self.count_valid_moves(cell) as i32 - self.count_valid_moves(opponent) as i32;
+
+        discs + corners * 25 + mobility * 5
+    }
+
+    /// Counts the discs of 'cell' that can never be flipped. This is
+    /// cautious, and may miss some: a disc counts as stable if along
+    /// each of the four lines through it, the line is full, or the disc
+    /// is next to the edge of the board or to a stable disc of its
+    /// own color.
+    pub fn count_stable_discs(&self, cell: Cell) -> i32 {
+        let width = self.get_width();
+        let index = |loc: Loc| loc.y * width + loc.x;
+        let mut stable = vec![false; self.get_width() * self.get_height()];
+
+        let full_lines: Vec<[bool; 4]> = self
+            .locations()
+            .map(|loc| {
+                let mut full = [false; 4];
+                for (axis, &(dx, dy)) in AXES.iter().enumerate() {
+                    let mut line = self
+                        .cells_from(loc, dx, dy)
+                        .chain(self.cells_from(loc, -dx, -dy));
+                    full[axis] = line.all(|l| self[l] != Cell::Empty);
+                }
+                full
+            })
+            .collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for loc in self.locations() {
+                if self[loc] != cell || stable[index(loc)] {
+                    continue;
+                }
+
+                let anchored = |dx: isize, dy: isize| match self.offset_within(loc, dx, dy) {
+                    None => true,
+                    Some(l) => stable[index(l)],
+                };
+
+                let is_stable = AXES.iter().enumerate().all(|(axis, &(dx, dy))| {
+                    full_lines[index(loc)][axis] || anchored(dx, dy) || anchored(-dx, -dy)
+                });
+
+                if is_stable {
+                    stable[index(loc)] = true;
+                    changed = true;
+                }
+            }
+        }
+
+        stable.iter().filter(|&&s| s).count() as i32
+    }
+
+    /// Counts, as 'cell's count less the opponent's, the corners held,
+    /// then the X-squares (diagonally inside a corner) and C-squares
+    /// (beside a corner) held next to empty corners.
+    fn measure_corners(&self, cell: Cell) -> (i32, i32, i32) {
+        let score = |loc: Loc| {
+            if self[loc] == cell {
+                1
+            } else if self[loc] == cell.flipped() {
+                -1
+            } else {
+                0
+            }
+        };
+
+        let right = self.get_width() - 1;
+        let bottom = self.get_height() - 1;
+        let corners = [
+            (Loc::new(0, 0), 1, 1),
+            (Loc::new(right, 0), -1, 1),
+            (Loc::new(0, bottom), 1, -1),
+            (Loc::new(right, bottom), -1, -1),
+        ];
+
+        let mut corner_score = 0;
+        let mut x_score = 0;
+        let mut c_score = 0;
+
+        for &(corner, dx, dy) in &corners {
+            if self[corner] != Cell::Empty {
+                corner_score += score(corner);
+            } else {
+                let inside = |dx, dy| self.offset_within(corner, dx, dy).map_or(0, score);
+                x_score += inside(dx, dy);
+                c_score += inside(dx, 0) + inside(0, dy);
+            }
+        }
+
+        (corner_score, x_score, c_score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    /// Makes an 8x8 board from rows like those Board::save() writes.
+    fn board_of(rows: [&str; 8]) -> Board {
+        let text = format!("size 8 8\n{}\n", rows.join("\n"));
+        Board::load(&mut text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn corners_are_stable() {
+        let board = board_of([
+            "X.......", "........", "........", "...OX...", "...XO...", "........", "........",
+            ".......O",
+        ]);
+        assert_eq!(board.count_stable_discs(Cell::Black), 1);
+        assert_eq!(board.count_stable_discs(Cell::White), 1);
+    }
+
+    #[test]
+    fn full_edges_are_stable() {
+        let board = board_of([
+            "XOXXOOXO", "........", "........", "...OX...", "...XO...", "........", "........",
+            "........",
+        ]);
+        assert_eq!(board.count_stable_discs(Cell::Black), 4);
+        assert_eq!(board.count_stable_discs(Cell::White), 4);
+    }
+
+    #[test]
+    fn discs_that_can_be_flipped_are_not_stable() {
+        // b1 could be outflanked along the top edge, and the
+        // discs in the middle in every direction.
+        let board = board_of([
+            ".X......", "........", "........", "...OX...", "...XO...", "........", "........",
+            "........",
+        ]);
+        assert_eq!(board.count_stable_discs(Cell::Black), 0);
+        assert_eq!(board.count_stable_discs(Cell::White), 0);
+
+        // Next to a corner of its own color, b1 is stable too,
+        // but not next to one of the opponent's.
+        let board = board_of([
+            "XXO.....", "O.......", "........", "...OX...", "...XO...", "........", "........",
+            "........",
+        ]);
+        assert_eq!(board.count_stable_discs(Cell::Black), 2);
+        assert_eq!(board.count_stable_discs(Cell::White), 0);
+    }
+
+    #[test]
+    fn evaluations_are_opposite_but_for_parity() {
+        for (width, height) in [(8, 8), (6, 6)] {
+            for seed in 0..4 {
+                let mut game = RandomGame::new(width, height, seed);
+                while !game.is_over() {
+                    let board = game.get_board();
+                    let total = (width * height) as i32;
+                    let empty = board.count_cells(Cell::Empty) as i32;
+                    let parity = Weights::for_phase(total - empty, total).parity * (empty % 2);
+
+                    let black = board.evaluate(Cell::Black);
+                    let white = board.evaluate(Cell::White);
+                    assert_eq!(black + white, 2 * parity);
+                    assert_eq!(
+                        board.evaluate_simply(Cell::Black),
+                        -board.evaluate_simply(Cell::White)
+                    );
+                    game.play_turn();
+                }
+            }
+        }
+    }
+}
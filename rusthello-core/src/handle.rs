@@ -0,0 +1,361 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::*;
+
+/// A shared reference to a game, which can be cloned and sent to other
+/// threads; user interfaces, network handlers and the computer's own
+/// search can all hold one. The command methods each lock the game for
+/// just as long as they need it.
+#[derive(Clone)]
+pub struct GameHandle {
+    game: Arc<Mutex<Game>>,
+}
+
+/// The reasons a command given to a GameHandle can be refused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandError {
+    /// The game has ended, so no more moves can be made.
+    GameOver,
+    /// It is the computer's turn, or a move is still being played out.
+    NotYourTurn,
+    /// The move given is not valid for the player whose turn it is.
+    InvalidMove(Loc),
+    /// The player whose turn it is has a valid move, so may not pass.
+    CannotPass,
+    /// There are no moves to undo.
+    NothingToUndo,
+    /// There are no undone moves to redo.
+    NothingToRedo,
+}
+
+impl GameHandle {
+    /// Creates a handle that owns 'game'.
+    pub fn new(game: Game) -> GameHandle {
+        GameHandle {
+            game: Arc::new(Mutex::new(game)),
+        }
+    }
+
+    /// Locks the game, to read it or to change it in ways the commands
+    /// don't cover. Don't hold the lock long, and don't call the command
+    /// methods while holding it; they take the lock themselves.
+    pub fn lock(&self) -> MutexGuard<'_, Game> {
+        // A thread that panicked while holding the lock can leave at worst
+        // a half-played movement, which the game can carry on from.
+        self.game.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Moves at 'loc' for the human player whose turn it is.
+    pub fn play(&self, loc: Loc) -> Result<(), CommandError> {
+        let mut game = self.lock();
+        check_human_turn(&mut game)?;
+
+        let mv = game.get_player_movement(loc);
+        if game.begin_immediate_movement(mv) {
+            Ok(())
+        } else {
+            Err(CommandError::InvalidMove(loc))
+        }
+    }
+
+    /// Passes for the human player whose turn it is; they may
+    /// pass only when they have no valid move.
+    pub fn pass(&self) -> Result<(), CommandError> {
+        let mut game = self.lock();
+        check_human_turn(&mut game)?;
+
+        if game.pass() {
+            Ok(())
+        } else {
+            Err(CommandError::CannotPass)
+        }
+    }
+
+    /// Takes back moves until it is a human player's turn again, so the
+    /// computer's reply goes with the player's move. If neither player
+    /// is human, this takes back just one move.
+    pub fn undo(&self) -> Result<(), CommandError> {
+        let mut game = self.lock();
+        if !game.undo() {
+            return Err(CommandError::NothingToUndo);
+        }
+
+        let any_human = has_human_player(&game);
+        while any_human && !game.is_human_turn() && game.undo() {}
+        Ok(())
+    }
+
+    /// Puts back undone moves until it is a human player's turn
+    /// again, in the same way as undo().
+    pub fn redo(&self) -> Result<(), CommandError> {
+        let mut game = self.lock();
+        if !game.redo() {
+            return Err(CommandError::NothingToRedo);
+        }
+
+        let any_human = has_human_player(&game);
+        while any_human && !game.is_human_turn() && game.redo() {}
+        Ok(())
+    }
+
+    /// Resigns the game for the player of 'cell', whether
+    /// or not it is their turn.
+    pub fn resign(&self, cell: Cell) -> Result<(), CommandError> {
+        if self.lock().resign(cell) {
+            Ok(())
+        } else {
+            Err(CommandError::GameOver)
+        }
+    }
+
+    /// Moves the game along: this plays any flips that are due and, if
+    /// it is the computer's turn and the game is not over, passes for it
+    /// or starts it choosing a move. The computer's move is played on its
+    /// own thread once chosen, so this should be called regularly, as each
+    /// frame is drawn.
+    pub fn advance(&self) {
+        let mut game = self.lock();
+        let cell = game.check_move();
+
+        if cell != Cell::Empty && !game.is_over() && !game.get_player(cell).is_human() {
+            if game.must_pass() {
+                game.pass();
+            } else {
+                let handle = self.clone();
+                game.begin_thinking(move |thought| {
+                    handle.lock().end_thinking(thought);
+                });
+            }
+        }
+    }
+}
+
+/// Checks that it is a human player's turn, and the game is not over.
+fn check_human_turn(game: &mut Game) -> Result<(), CommandError> {
+    if game.is_over() {
+        Err(CommandError::GameOver)
+    } else if game.is_human_turn() {
+        Ok(())
+    } else {
+        Err(CommandError::NotYourTurn)
+    }
+}
+
+fn has_human_player(game: &Game) -> bool {
+    game.get_player(Cell::Black).is_human() || game.get_player(Cell::White).is_human()
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::GameOver => write!(f, "the game is over"),
+            CommandError::NotYourTurn => write!(f, "it is not your turn"),
+            CommandError::InvalidMove(loc) => write!(f, "{} is not a valid move", loc),
+            CommandError::CannotPass => write!(f, "you have a valid move, so cannot pass"),
+            CommandError::NothingToUndo => write!(f, "there is nothing to undo"),
+            CommandError::NothingToRedo => write!(f, "there is nothing to redo"),
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::testing::*;
+
+    fn loc(name: &str) -> Loc {
+        name.parse().unwrap()
+    }
+
+    /// A handle on a new game whose moves are played out at once.
+    fn new_handle() -> GameHandle {
+        let mut game = Game::new(Board::new(8, 8));
+        game.set_flip_delay(Duration::ZERO);
+        game.into_handle()
+    }
+
+    /// A handle on a small game in which the player to move must pass,
+    /// found by playing random games until one reaches such a position.
+    fn handle_that_must_pass() -> GameHandle {
+        for seed in 0.. {
+            let mut random = RandomGame::new(4, 4, seed);
+            let mut transcript = Transcript::new();
+
+            while !random.is_over() {
+                let play = random.play_turn();
+                if play == Play::Pass {
+                    let mut game = Game::from_transcript(Board::new(4, 4), &transcript).unwrap();
+                    game.set_flip_delay(Duration::ZERO);
+                    return game.into_handle();
+                }
+                transcript.push(play);
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn valid_moves_are_played() {
+        let handle = new_handle();
+        assert_eq!(handle.play(loc("f5")), Ok(()));
+
+        let mut game = handle.lock();
+        assert_eq!(game.check_move(), Cell::White);
+        assert_eq!(game.to_board()[loc("e5")], Cell::Black);
+        assert_eq!(game.to_board().count_cells(Cell::Black), 4);
+    }
+
+    #[test]
+    fn invalid_moves_are_rejected() {
+        let handle = new_handle();
+
+        // Taken, or flipping nothing.
+        for name in ["d4", "e5", "a1", "f6"] {
+            assert_eq!(
+                handle.play(loc(name)),
+                Err(CommandError::InvalidMove(loc(name)))
+            );
+        }
+        assert_eq!(handle.lock().to_transcript(), Transcript::new());
+
+        // Valid for black, but it is now white's turn.
+        handle.play(loc("f5")).unwrap();
+        assert_eq!(
+            handle.play(loc("c4")),
+            Err(CommandError::InvalidMove(loc("c4")))
+        );
+        assert_eq!(handle.lock().get_next_move(), Cell::White);
+    }
+
+    #[test]
+    fn moves_wait_for_the_computer_and_for_flips() {
+        let handle = new_handle();
+        handle
+            .lock()
+            .set_player(Cell::White, Player::Computer(Search::new(1)));
+
+        handle.play(loc("f5")).unwrap();
+        assert_eq!(handle.play(loc("f6")), Err(CommandError::NotYourTurn));
+        assert_eq!(handle.pass(), Err(CommandError::NotYourTurn));
+
+        // While a move is still flipping, nobody may move.
+        let handle = new_handle();
+        let clock = Arc::new(ManualClock::new());
+        {
+            let mut game = handle.lock();
+            game.set_clock(clock.clone());
+            game.set_flip_delay(Duration::from_millis(100));
+        }
+        handle.play(loc("f5")).unwrap();
+        assert_eq!(handle.play(loc("f6")), Err(CommandError::NotYourTurn));
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(handle.play(loc("f6")), Ok(()));
+    }
+
+    #[test]
+    fn passes_are_allowed_only_without_a_move() {
+        let handle = new_handle();
+        assert_eq!(handle.pass(), Err(CommandError::CannotPass));
+
+        let handle = handle_that_must_pass();
+        let cell = handle.lock().get_next_move();
+        let moves = handle.lock().to_board().count_valid_moves(cell.flipped());
+        assert!(moves > 0);
+
+        // Every move is invalid, as the player has none.
+        let board = handle.lock().to_board().clone();
+        for l in board.locations() {
+            assert_eq!(handle.play(l), Err(CommandError::InvalidMove(l)));
+        }
+
+        assert_eq!(handle.pass(), Ok(()));
+        assert_eq!(handle.lock().get_next_move(), cell.flipped());
+        assert_eq!(handle.lock().get_last_pass(), Some(cell));
+        assert_eq!(handle.pass(), Err(CommandError::CannotPass));
+    }
+
+    #[test]
+    fn finished_games_take_no_more_commands() {
+        // Black takes every white disc in nine moves.
+        let transcript: Transcript = "d3c3b3d2e1d6d7e3f4".parse().unwrap();
+        let game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        let handle = game.into_handle();
+
+        assert_eq!(handle.play(loc("a1")), Err(CommandError::GameOver));
+        assert_eq!(handle.pass(), Err(CommandError::GameOver));
+        assert_eq!(handle.resign(Cell::Black), Err(CommandError::GameOver));
+        assert_eq!(handle.undo(), Ok(()));
+    }
+
+    #[test]
+    fn finished_games_are_not_advanced() {
+        let transcript: Transcript = "d3c3b3d2e1d6d7e3f4".parse().unwrap();
+        let mut game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        game.set_player(Cell::White, Player::Computer(Search::new(1)));
+        game.set_player(Cell::Black, Player::Computer(Search::new(1)));
+        let handle = game.into_handle();
+
+        for _ in 0..100 {
+            handle.advance();
+            assert!(!handle.lock().is_thinking());
+        }
+    }
+
+    #[test]
+    fn resigning_ends_the_game_until_undone() {
+        let handle = new_handle();
+        handle.play(loc("f5")).unwrap();
+
+        // Black may resign on white's turn.
+        assert_eq!(handle.resign(Cell::Black), Ok(()));
+        assert_eq!(handle.lock().get_resigned(), Some(Cell::Black));
+        assert_eq!(handle.play(loc("f6")), Err(CommandError::GameOver));
+        assert_eq!(handle.pass(), Err(CommandError::GameOver));
+        assert_eq!(handle.resign(Cell::White), Err(CommandError::GameOver));
+        assert_eq!(handle.redo(), Err(CommandError::NothingToRedo));
+
+        assert_eq!(handle.undo(), Ok(()));
+        assert_eq!(handle.lock().get_resigned(), None);
+        assert_eq!(handle.play(loc("f6")), Ok(()));
+    }
+
+    #[test]
+    fn undo_and_redo_skip_the_computers_moves() {
+        let handle = new_handle();
+        assert_eq!(handle.undo(), Err(CommandError::NothingToUndo));
+        assert_eq!(handle.redo(), Err(CommandError::NothingToRedo));
+
+        handle.play(loc("f5")).unwrap();
+        handle.play(loc("f6")).unwrap();
+
+        // With two human players, each move is undone by itself.
+        assert_eq!(handle.undo(), Ok(()));
+        assert_eq!(handle.lock().to_transcript().to_string(), "f5");
+        assert_eq!(handle.redo(), Ok(()));
+        assert_eq!(handle.lock().to_transcript().to_string(), "f5f6");
+
+        // With the computer playing white, its reply goes with black's move.
+        handle
+            .lock()
+            .set_player(Cell::White, Player::Computer(Search::new(1)));
+        assert_eq!(handle.undo(), Ok(()));
+        assert_eq!(handle.lock().to_transcript(), Transcript::new());
+        assert_eq!(handle.lock().get_next_move(), Cell::Black);
+
+        assert_eq!(handle.redo(), Ok(()));
+        assert_eq!(handle.lock().to_transcript().to_string(), "f5f6");
+        assert_eq!(handle.redo(), Err(CommandError::NothingToRedo));
+
+        // A new move discards what was undone.
+        handle.undo().unwrap();
+        handle.play(loc("d3")).unwrap();
+        assert_eq!(handle.redo(), Err(CommandError::NothingToRedo));
+    }
+}
@@ -0,0 +1,83 @@
+//! Iterator adapters the rules use, which other tools may find useful too.
+
+/// An extension to provide take_up_to() for every iterator.
+pub trait IterExt<T> {
+    /// This yields each item of the input until one passes the predicate;
+    /// it then returns that item, and stops afterwards.
+    fn take_up_to<P>(self, pred: P) -> TakeUpTo<Self, P>
+    where
+        P: FnMut(&T) -> bool,
+        Self: Sized,
+    {
+        TakeUpTo {
+            iterator: self,
+            pred,
+            done: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = T>, T> IterExt<T> for I {}
+
+/// Holds the state needed for take_up_to.
+pub struct TakeUpTo<I, P> {
+    iterator: I,
+    pred: P,
+    done: bool,
+}
+
+impl<I, P> Iterator for TakeUpTo<I, P>
+where
+    I: Iterator,
+    P: FnMut(&I::Item) -> bool,
+{
+    type Item = I::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            None
+        } else if let Some(item) = self.iterator.next() {
+            self.done = (self.pred)(&item);
+            Some(item)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_up_to_includes_the_matching_item() {
+        let taken: Vec<_> = (1..=5).take_up_to(|&n| n == 3).collect();
+        assert_eq!(taken, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn take_up_to_can_stop_at_the_first_item() {
+        let taken: Vec<_> = (1..=5).take_up_to(|&n| n == 1).collect();
+        assert_eq!(taken, vec![1]);
+    }
+
+    #[test]
+    fn take_up_to_takes_everything_without_a_match() {
+        let taken: Vec<_> = (1..=5).take_up_to(|&n| n > 10).collect();
+        assert_eq!(taken, vec![1, 2, 3, 4, 5]);
+
+        let taken: Vec<i32> = std::iter::empty().take_up_to(|_| true).collect();
+        assert!(taken.is_empty());
+    }
+
+    #[test]
+    fn take_up_to_reads_no_further_than_the_match() {
+        let mut read = 0;
+        let mut taken = (1..=5).inspect(|_| read += 1).take_up_to(|&n| n == 2);
+
+        assert_eq!(taken.next(), Some(1));
+        assert_eq!(taken.next(), Some(2));
+        assert_eq!(taken.next(), None);
+        assert_eq!(taken.next(), None);
+        assert_eq!(read, 2);
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+
+/// How well the computer plays.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Level {
+    /// Plays any valid move, at random.
+    Beginner,
+    /// Plays the move that looks best right now, without looking ahead.
+    Easy,
+    /// Looks a couple of moves ahead, judging positions crudely.
+    Medium,
+    /// Looks further ahead, and judges positions carefully.
+    Hard,
+    /// Looks further still, and plays the end of the game perfectly.
+    Expert,
+}
+
+impl Level {
+    /// Every level, from weakest to strongest.
+    pub const ALL: [Level; 5] = [
+        Level::Beginner,
+        Level::Easy,
+        Level::Medium,
+        Level::Hard,
+        Level::Expert,
+    ];
+
+    /// The level's name, as given on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Beginner => "beginner",
+            Level::Easy => "easy",
+            Level::Medium => "medium",
+            Level::Hard => "hard",
+            Level::Expert => "expert",
+        }
+    }
+
+    /// Finds the level with the name given.
+    pub fn from_name(name: &str) -> Option<Level> {
+        Level::ALL
+            .iter()
+            .copied()
+            .find(|level| level.name() == name)
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name();
+        write!(f, "{}{}", name[..1].to_uppercase(), &name[1..])
+    }
+}
@@ -0,0 +1,572 @@
+#![warn(missing_docs)]
+
+//! The rules of Othello (also known as Reversi), along with computer
+//! players for it.
+//!
+//! A [`Game`] owns the [`Board`] and knows whose turn it is; it also
+//! keeps a history of moves so they can be undone and redone. Each side
+//! is played by a [`Player`], which is either a human, an alpha-beta
+//! [`Search`], or a [`MonteCarlo`] tree search. Near the end of a game,
+//! [`Board::solve_endgame`] finds its exact [`Solution`].
+//!
+//! The [`iterext`] module holds the iterator adapters the rules use.
+//!
+//! This crate has no user interface of its own.
+
+use std::sync::Arc;
+use std::time::*;
+
+mod bitboard;
+mod board;
+mod book;
+mod cell;
+mod clock;
+mod endgame;
+mod eval;
+mod handle;
+pub mod iterext;
+mod level;
+mod mcts;
+mod movement;
+mod perft;
+mod player;
+mod random;
+mod savefile;
+mod search;
+#[cfg(test)]
+mod testing;
+mod thinking;
+mod transcript;
+mod transposition;
+
+pub use bitboard::*;
+pub use board::*;
+pub use book::*;
+pub use cell::*;
+pub use clock::*;
+pub use endgame::*;
+pub use handle::*;
+pub use level::*;
+pub use mcts::*;
+pub use movement::*;
+pub use player::*;
+pub use random::*;
+pub use search::*;
+pub use thinking::*;
+pub use transcript::*;
+pub use transposition::*;
+
+/// Game holds onto the board and all game state; it knows
+/// who gets the next move, and tracks on-going moves as they
+/// happen. It also keeps the history of moves, so they can be
+/// undone and redone.
+pub struct Game {
+    board: Board,
+    next_move: Cell,
+    ongoing_movement: Movement,
+    next_move_time: Instant,
+    clock: Arc<dyn Clock>,
+    flip_delay: Duration,
+    black_player: Player,
+    white_player: Player,
+    history: Vec<Snapshot>,
+    undone: Vec<Snapshot>,
+    thinking: Option<Thinking>,
+    thoughts_started: u64,
+    resigned: Option<Cell>,
+}
+
+/// Records the state of the game between moves, so that
+/// we can go back to it. It also records the move or pass
+/// made from this state; for undone moves, this is the move
+/// that led to this state instead.
+#[derive(Clone)]
+struct Snapshot {
+    board: Board,
+    next_move: Cell,
+    play: Play,
+}
+
+impl Game {
+    /// How long each disc takes to flip, unless set otherwise.
+    pub const DEFAULT_FLIP_DELAY: Duration = Duration::from_millis(100);
+
+    /// Starts a new game on the board given, with black to move
+    /// and two human players.
+    pub fn new(board: Board) -> Game {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        let start = clock.now();
+
+        Game {
+            board,
+            next_move: Cell::Black,
+            ongoing_movement: Movement::default(),
+            next_move_time: start,
+            clock,
+            flip_delay: Game::DEFAULT_FLIP_DELAY,
+            black_player: Player::Human,
+            white_player: Player::Human,
+            history: Vec::new(),
+            undone: Vec::new(),
+            thinking: None,
+            thoughts_started: 0,
+            resigned: None,
+        }
+    }
+
+    /// Creates a GameHandle refering to this game, so that it
+    /// can be shared between views and threads.
+    pub fn into_handle(self) -> GameHandle {
+        GameHandle::new(self)
+    }
+
+    /// Creates a game by playing the moves of a transcript on the board
+    /// given; the game's history holds these moves, so they can be undone.
+    /// This fails if any move in the transcript is not valid.
+    pub fn from_transcript(board: Board, transcript: &Transcript) -> Result<Game, TranscriptError> {
+        let mut game = Game::new(board);
+
+        for (index, &play) in transcript.get_moves().iter().enumerate() {
+            let played = match play {
+                Play::Move(loc) => {
+                    // Transcripts often leave passes out, so we
+                    // pass when the move requires it.
+                    if game.must_pass() {
+                        game.pass();
+                    }
+
+                    let mv = game.get_player_movement(loc);
+                    game.begin_movement(mv)
+                }
+                Play::Pass => game.pass(),
+            };
+
+            if !played {
+                return Err(TranscriptError::InvalidMove {
+                    number: index + 1,
+                    play,
+                });
+            }
+            game.finish_movement();
+        }
+
+        Ok(game)
+    }
+
+    /// Returns a transcript of the moves and passes played so far; this
+    /// does not include any moves that have been undone.
+    pub fn to_transcript(&self) -> Transcript {
+        let mut transcript = Transcript::new();
+        for snapshot in &self.history {
+            transcript.push(snapshot.play);
+        }
+        transcript
+    }
+
+    /// Provides an immutable reference to the board.
+    pub fn to_board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Checks the game staet and returns the cell of the player
+    /// whose move it now is. This will also apply ongoing moves, so it
+    /// can change the game state. If there are any ongoing moves pending
+    /// when it returns, it returns Empty- nobody can move until outstanding
+    /// moves clear.
+    ///
+    /// Outsanding moves are timed by the game's clock; this may do nothing
+    /// (and return Empty) if there are outstanding moves that are not yet due.
+    pub fn check_move(&mut self) -> Cell {
+        if self.ongoing_movement.is_valid() {
+            let now = self.clock.now();
+            while now >= self.next_move_time {
+                self.next_move_time += self.flip_delay;
+
+                if !self.ongoing_movement.play_one(&mut self.board) {
+                    break;
+                }
+
+                if !self.ongoing_movement.is_valid() {
+                    self.end_movement();
+                }
+            }
+        }
+
+        self.get_next_move()
+    }
+
+    /// Sets the clock that times the flips of each movement.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.next_move_time = clock.now();
+        self.clock = clock;
+    }
+
+    /// Sets how long each disc takes to flip; if this is zero,
+    /// each movement is played out at once.
+    pub fn set_flip_delay(&mut self, delay: Duration) {
+        self.flip_delay = delay;
+    }
+
+    /// Returns the cell of the player whose move it is, like check_move(),
+    /// but without playing any ongoing movement.
+    pub fn get_next_move(&self) -> Cell {
+        if self.ongoing_movement.is_valid() || self.resigned.is_some() {
+            Cell::Empty
+        } else {
+            self.next_move
+        }
+    }
+
+    /// True if the player whose turn it is has no valid move, and so
+    /// must pass. This is false if the game is over.
+    pub fn must_pass(&self) -> bool {
+        let cell = self.get_next_move();
+        cell != Cell::Empty && !self.board.is_game_over() && !self.board.has_valid_move(cell)
+    }
+
+    /// True if the game has ended, either because neither player
+    /// can move or because a player resigned.
+    pub fn is_over(&self) -> bool {
+        self.resigned.is_some() || self.board.is_game_over()
+    }
+
+    /// Ends the game with a win for the player who did not resign;
+    /// either player may resign, whether or not it is their turn.
+    /// This returns false if the game is already over.
+    pub fn resign(&mut self, cell: Cell) -> bool {
+        if cell == Cell::Empty || self.is_over() {
+            false
+        } else {
+            self.thinking = None;
+            self.finish_movement();
+            self.undone.clear();
+            self.resigned = Some(cell);
+            true
+        }
+    }
+
+    /// Returns the cell of the player who resigned, if one did.
+    pub fn get_resigned(&self) -> Option<Cell> {
+        self.resigned
+    }
+
+    /// Passes the turn to the other player; this is allowed only when
+    /// must_pass() is true, and returns false if it is not.
+    pub fn pass(&mut self) -> bool {
+        if self.must_pass() {
+            self.history.push(self.take_snapshot(Play::Pass));
+            self.undone.clear();
+            self.next_move = self.next_move.flipped();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// If the last thing to happen in the game was a pass, this returns
+    /// the cell of the player who passed.
+    pub fn get_last_pass(&self) -> Option<Cell> {
+        match self.history.last() {
+            Some(snapshot) if snapshot.play == Play::Pass => Some(snapshot.next_move),
+            _ => None,
+        }
+    }
+
+    /// Constructs a movement for a move at the location indicated. This
+    /// can result in an invalid move, if 'loc' is not a valid location
+    /// or it is not anyone's turn.
+    pub fn get_player_movement(&self, loc: Loc) -> Movement {
+        if self.next_move != Cell::Empty && self.board.contains(loc) {
+            Movement::new(&self.board, loc, self.next_move)
+        } else {
+            Movement::default()
+        }
+    }
+
+    /// Returns the player who moves for 'cell'.
+    pub fn get_player(&self, cell: Cell) -> &Player {
+        match cell {
+            Cell::White => &self.white_player,
+            _ => &self.black_player,
+        }
+    }
+
+    /// Sets the player who moves for 'cell'.
+    pub fn set_player(&mut self, cell: Cell, player: Player) {
+        self.thinking = None;
+
+        match cell {
+            Cell::Black => self.black_player = player,
+            Cell::White => self.white_player = player,
+            Cell::Empty => {}
+        }
+    }
+
+    /// True if it is the turn of a human player, so the game must wait
+    /// for them to move. This is false while a movement is ongoing.
+    pub fn is_human_turn(&mut self) -> bool {
+        match self.check_move() {
+            Cell::Empty => false,
+            cell => self.get_player(cell).is_human(),
+        }
+    }
+
+    /// If it is the computer's turn, this starts it choosing a move on
+    /// another thread, and returns true. When it has chosen, the move is
+    /// passed to 'deliver', which should arrange for end_thinking() to be
+    /// called with it. This returns false if it is not the computer's
+    /// turn, if the game is over, or if the computer is already thinking.
+    pub fn begin_thinking(&mut self, deliver: impl FnOnce(Thought) + Send + 'static) -> bool {
+        let cell = self.get_next_move();
+
+        if cell == Cell::Empty
+            || self.is_over()
+            || self.get_player(cell).is_human()
+            || self.thinking.is_some()
+        {
+            false
+        } else {
+            self.thoughts_started += 1;
+            let id = self.thoughts_started;
+            let player = self.get_player(cell);
+            self.thinking = Some(Thinking::start(id, player, &self.board, cell, deliver));
+            true
+        }
+    }
+
+    /// Plays the move the computer chose, and returns true. The move plays
+    /// out from now, one flip per delay, however long the search took. If
+    /// the game has moved on since it started thinking- say, because a move
+    /// was undone- this ignores the move and returns false.
+    pub fn end_thinking(&mut self, thought: Thought) -> bool {
+        match &self.thinking {
+            Some(thinking) if thinking.get_id() == thought.id => {
+                self.thinking = None;
+                if self.begin_movement(thought.movement) {
+                    self.next_move_time = self.clock.now();
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// True while the computer is choosing a move.
+    pub fn is_thinking(&self) -> bool {
+        self.thinking.is_some()
+    }
+
+    /// This plays a move. The move will play out over time, and
+    /// it will switch to a new player's turn only when complete.Duration
+    ///
+    /// This returns false if the movement is invalid, or if another
+    /// movement is ongoing. In this case no new movement is begun,
+    pub fn begin_movement(&mut self, mv: Movement) -> bool {
+        if self.ongoing_movement.is_valid() || self.resigned.is_some() {
+            false
+        } else if let Some(loc) = mv.get_loc() {
+            self.history.push(self.take_snapshot(Play::Move(loc)));
+            self.undone.clear();
+            self.ongoing_movement = mv;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// This plays a move, like begin_movement. This will also speed the move
+    /// up slightly so the first flip will occur at once.
+    pub fn begin_immediate_movement(&mut self, mv: Movement) -> bool {
+        if self.begin_movement(mv) {
+            self.next_move_time = self.clock.now();
+            self.check_move();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes back the last move, restoring the board and turn as they
+    /// were before it. Any ongoing movement is completed first, so it is
+    /// the move that is undone, and if the computer is thinking, it stops.
+    /// After a resignation, this takes back the resignation instead of
+    /// a move. This returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        self.thinking = None;
+        self.finish_movement();
+
+        if self.resigned.take().is_some() {
+            true
+        } else if let Some(snapshot) = self.history.pop() {
+            self.undone.push(self.take_snapshot(snapshot.play));
+            self.restore_snapshot(snapshot);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Puts back the last move undone. This returns false if there is
+    /// nothing to redo; making a new move discards any undone moves.
+    pub fn redo(&mut self) -> bool {
+        self.thinking = None;
+        self.finish_movement();
+
+        if let Some(snapshot) = self.undone.pop() {
+            self.history.push(self.take_snapshot(snapshot.play));
+            self.restore_snapshot(snapshot);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Plays any remaining flips of the ongoing movement at once.
+    fn finish_movement(&mut self) {
+        if self.ongoing_movement.is_valid() {
+            while self.ongoing_movement.play_one(&mut self.board) {}
+            self.end_movement();
+        }
+    }
+
+    /// Called when a movement has been completely played, this passes
+    /// the turn to the other player. If they have no valid move, they
+    /// must then pass.
+    fn end_movement(&mut self) {
+        self.next_move = self.next_move.flipped();
+    }
+
+    fn take_snapshot(&self, play: Play) -> Snapshot {
+        Snapshot {
+            board: self.board.clone(),
+            next_move: self.next_move,
+            play,
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.board = snapshot.board;
+        self.next_move = snapshot.next_move;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    /// Starts a game part way through, on a manual clock, and returns
+    /// it with the clock and the move for black that flips the most.
+    fn game_on_manual_clock() -> (Game, Arc<ManualClock>, Movement) {
+        let transcript: Transcript = "f5d6c3d3c4f4f6f3e6e7".parse().unwrap();
+        let mut game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        let clock = Arc::new(ManualClock::new());
+        game.set_clock(clock.clone());
+
+        let moves = game.to_board().find_valid_moves(Cell::Black);
+        let mv = moves
+            .into_iter()
+            .max_by_key(|mv| mv.get_flip_count())
+            .unwrap();
+        assert!(mv.get_flip_count() >= 2);
+        (game, clock, mv)
+    }
+
+    #[test]
+    fn movements_flip_one_disc_per_delay() {
+        let (mut game, clock, mv) = game_on_manual_clock();
+        let delay = Duration::from_millis(100);
+        let flips = mv.get_flip_count();
+        game.set_flip_delay(delay);
+
+        // The disc is placed at once, but nothing flips yet.
+        let black = game.to_board().count_cells(Cell::Black);
+        assert!(game.begin_immediate_movement(mv));
+        assert_eq!(game.to_board().count_cells(Cell::Black), black + 1);
+
+        clock.advance(delay - Duration::from_millis(1));
+        assert_eq!(game.check_move(), Cell::Empty);
+        assert_eq!(game.to_board().count_cells(Cell::Black), black + 1);
+
+        clock.advance(Duration::from_millis(1));
+        for flipped in 1..=flips {
+            let next = game.check_move();
+            assert_eq!(
+                game.to_board().count_cells(Cell::Black),
+                black + 1 + flipped
+            );
+
+            if flipped < flips {
+                assert_eq!(next, Cell::Empty);
+                clock.advance(delay);
+            } else {
+                assert_eq!(next, Cell::White);
+            }
+        }
+    }
+
+    #[test]
+    fn computer_moves_flip_one_disc_per_delay() {
+        let (mut game, clock, _) = game_on_manual_clock();
+        let delay = Duration::from_millis(100);
+        game.set_flip_delay(delay);
+        game.set_player(Cell::Black, Player::Computer(Search::new(1)));
+
+        let (send, receive) = mpsc::channel();
+        assert!(game.begin_thinking(move |thought| send.send(thought).unwrap()));
+        let thought = receive.recv().unwrap();
+        assert!(thought.movement.get_flip_count() >= 1);
+
+        // The search took far longer than one flip, but the
+        // move still starts with just the disc placed.
+        clock.advance(Duration::from_secs(2));
+        let black = game.to_board().count_cells(Cell::Black);
+        assert!(game.end_thinking(thought));
+        assert_eq!(game.check_move(), Cell::Empty);
+        assert_eq!(game.to_board().count_cells(Cell::Black), black + 1);
+
+        clock.advance(delay);
+        game.check_move();
+        assert_eq!(game.to_board().count_cells(Cell::Black), black + 2);
+    }
+
+    #[test]
+    fn finished_games_do_not_start_thinking() {
+        // Black takes every white disc in nine moves.
+        let transcript: Transcript = "d3c3b3d2e1d6d7e3f4".parse().unwrap();
+        let mut game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        game.set_player(Cell::White, Player::Computer(Search::new(1)));
+
+        assert!(game.is_over());
+        assert!(!game.begin_thinking(|_| panic!("a finished game was searched")));
+        assert!(!game.is_thinking());
+    }
+
+    #[test]
+    fn movements_wait_for_the_clock() {
+        let (mut game, _clock, mv) = game_on_manual_clock();
+        let black = game.to_board().count_cells(Cell::Black);
+        assert!(game.begin_immediate_movement(mv));
+
+        // However many times we check, the clock has not moved.
+        for _ in 0..10 {
+            assert_eq!(game.check_move(), Cell::Empty);
+        }
+        assert_eq!(game.to_board().count_cells(Cell::Black), black + 1);
+    }
+
+    #[test]
+    fn zero_delay_plays_movements_at_once() {
+        let (mut game, _clock, mv) = game_on_manual_clock();
+        let black = game.to_board().count_cells(Cell::Black);
+        let flips = mv.get_flip_count();
+        game.set_flip_delay(Duration::ZERO);
+
+        assert!(game.begin_movement(mv));
+        assert_eq!(game.check_move(), Cell::White);
+        assert_eq!(game.to_board().count_cells(Cell::Black), black + 1 + flips);
+    }
+}
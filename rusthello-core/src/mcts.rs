@@ -0,0 +1,413 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::*;
+
+/// How long MonteCarlo thinks about each move, unless told otherwise.
+pub const DEFAULT_THINK_TIME: Duration = Duration::from_secs(1);
+
+/// How many games MonteCarlo plays out for each move when it has
+/// no time limit, unless told otherwise.
+pub const DEFAULT_ITERATIONS: usize = 10_000;
+
+/// How much UCT favors trying moves that have been tried less; this
+/// is the usual square root of 2.
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+/// Chooses moves by Monte Carlo tree search: it plays many games out to
+/// the end from the current position, making mostly random moves, and
+/// picks the move that led to the most wins. It grows a tree of the moves
+/// it has tried, and uses UCT (upper confidence bounds applied to trees)
+/// to decide which to try next, balancing the moves that have done well
+/// against those that have been tried least.
+///
+/// This needs no evaluation of positions, so it plays reasonably on
+/// boards of any size.
+#[derive(Clone)]
+pub struct MonteCarlo {
+    iterations: usize,
+    think_time: Option<Duration>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+/// A position in the tree the search grows.
+struct Node {
+    board: Board,
+    /// The player to move in this position.
+    cell: Cell,
+    /// The move that led here from the parent node; this is
+    /// None for the root, and for a pass.
+    loc: Option<Loc>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// The moves from here that have no node yet; if the player to
+    /// move must pass, this holds an invalid movement for the pass.
+    untried: Vec<Movement>,
+    visits: u32,
+    /// The games won by the player who moved into this position, with
+    /// draws counting as half a win.
+    wins: f64,
+}
+
+impl MonteCarlo {
+    /// Creates a search that plays out 'iterations' games for each move.
+    pub fn new(iterations: usize) -> MonteCarlo {
+        MonteCarlo {
+            iterations: iterations.max(1),
+            think_time: None,
+            cancel: None,
+        }
+    }
+
+    /// Creates a search that plays out as many games as it can in
+    /// the default think time. Without the time limit, it plays
+    /// out DEFAULT_ITERATIONS games.
+    pub fn with_default_time() -> MonteCarlo {
+        MonteCarlo {
+            iterations: DEFAULT_ITERATIONS,
+            think_time: Some(DEFAULT_THINK_TIME),
+            cancel: None,
+        }
+    }
+
+    /// Sets how long the search may take to choose each move; with a
+    /// time limit, it plays out as many games as it can in that time
+    /// rather than a fixed number. With None, it plays out the number
+    /// of games it was created with.
+    pub fn set_think_time(&mut self, think_time: Option<Duration>) {
+        self.think_time = think_time;
+    }
+
+    /// Sets a flag that stops the search early when it becomes true;
+    /// the move found then should not be used.
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = Some(cancel);
+    }
+
+    /// Returns the best move for 'cell' on the board given. This returns an
+    /// invalid movement if 'cell' has no valid moves.
+    pub fn find_move(&self, board: &Board, cell: Cell) -> Movement {
+        let mut moves = board.find_valid_moves(cell);
+        if moves.len() <= 1 {
+            return moves.pop().unwrap_or_default();
+        }
+
+        let start = Instant::now();
+        let deadline = self.think_time.and_then(|t| start.checked_add(t));
+        let mut random = Random::from_time();
+        let mut tree = vec![Node::new(board.clone(), cell, None, None)];
+
+        for iteration in 0.. {
+            let done = match deadline {
+                Some(deadline) => iteration > 0 && Instant::now() >= deadline,
+                None => iteration >= self.iterations,
+            };
+            let canceled = self
+                .cancel
+                .as_ref()
+                .is_some_and(|c| c.load(Ordering::Relaxed));
+            if done || canceled {
+                break;
+            }
+
+            let selected = select(&tree);
+            let leaf = expand(&mut tree, selected, &mut random);
+            let winner = play_out(&tree[leaf].board, tree[leaf].cell, &mut random);
+            propagate(&mut tree, leaf, winner);
+        }
+
+        // The move tried most often is the one the search trusts most.
+        let best = tree[0]
+            .children
+            .iter()
+            .max_by_key(|&&child| tree[child].visits);
+        let loc = best.and_then(|&child| tree[child].loc);
+        moves
+            .into_iter()
+            .find(|mv| mv.get_loc() == loc)
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for MonteCarlo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MCTS")
+    }
+}
+
+impl Node {
+    fn new(board: Board, cell: Cell, loc: Option<Loc>, parent: Option<usize>) -> Node {
+        let mut untried = board.find_valid_moves(cell);
+        if untried.is_empty() && !board.is_game_over() {
+            untried.push(Movement::default());
+        }
+
+        Node {
+            board,
+            cell,
+            loc,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+
+    /// The UCT score of this node, whose parent has been visited
+    /// 'parent_visits' times; the child with the highest score is
+    /// the one to explore.
+    fn get_uct_score(&self, parent_visits: u32) -> f64 {
+        let visits = self.visits as f64;
+        let exploration = (f64::from(parent_visits).ln() / visits).sqrt();
+        self.wins / visits + EXPLORATION * exploration
+    }
+}
+
+/// Starting at the root, follows the children with the best UCT
+/// scores until it reaches a node with moves not yet tried, or one
+/// where the game is over.
+fn select(tree: &[Node]) -> usize {
+    let mut index = 0;
+
+    while tree[index].untried.is_empty() && !tree[index].children.is_empty() {
+        let node = &tree[index];
+        let score = |&child: &usize| tree[child].get_uct_score(node.visits);
+        index = *node
+            .children
+            .iter()
+            .max_by(|a, b| score(a).total_cmp(&score(b)))
+            .unwrap();
+    }
+
+    index
+}
+
+/// Adds a node to the tree for one of the untried moves from the node
+/// given, chosen at random, and returns it. If every move has been tried,
+/// the game must be over, and this returns the node given.
+fn expand(tree: &mut Vec<Node>, index: usize, random: &mut Random) -> usize {
+    if tree[index].untried.is_empty() {
+        return index;
+    }
+
+    let choice = random.below(tree[index].untried.len());
+    let mv = tree[index].untried.swap_remove(choice);
+    let mut board = tree[index].board.clone();
+    mv.play(&mut board);
+
+    let child = tree.len();
+    let cell = tree[index].cell.flipped();
+    tree.push(Node::new(board, cell, mv.get_loc(), Some(index)));
+    tree[index].children.push(child);
+    child
+}
+
+/// Plays the game out from the board given, with 'cell' to move, and
+/// returns the winner; this is Empty for a draw. The moves are random,
+/// except that a player always takes a corner if it can; this small
+/// amount of sense makes the results much more telling.
+fn play_out(board: &Board, cell: Cell, random: &mut Random) -> Cell {
+    if let Some(bits) = board.get_bitboard() {
+        return play_out_bits(*bits, cell, random);
+    }
+
+    let mut board = board.clone();
+    let mut cell = cell;
+    let mut passed = false;
+
+    loop {
+        let mv = choose_random_move(&board, cell, random);
+
+        if mv.is_valid() {
+            mv.play(&mut board);
+            passed = false;
+        } else if passed {
+            break;
+        } else {
+            passed = true;
+        }
+
+        cell = cell.flipped();
+    }
+
+    let black = board.count_cells(Cell::Black);
+    let white = board.count_cells(Cell::White);
+    find_winner(black, white)
+}
+
+/// Works like play_out(), but on a bitboard.
+fn play_out_bits(bits: Bitboard, cell: Cell, random: &mut Random) -> Cell {
+    const CORNERS: u64 = 0x8100_0000_0000_0081;
+
+    let mut bits = bits;
+    let mut cell = cell;
+    let mut passed = false;
+
+    loop {
+        let moves = bits.valid_moves(cell);
+
+        if moves != 0 {
+            let choices = if moves & CORNERS != 0 {
+                moves & CORNERS
+            } else {
+                moves
+            };
+            let choice = random.below(choices.count_ones() as usize);
+            let loc = Bitboard::locations(choices).nth(choice).unwrap();
+            bits = bits.play(cell, loc);
+            passed = false;
+        } else if passed {
+            break;
+        } else {
+            passed = true;
+        }
+
+        cell = cell.flipped();
+    }
+
+    let black = bits.get_mask(Cell::Black).count_ones();
+    let white = bits.get_mask(Cell::White).count_ones();
+    find_winner(black as usize, white as usize)
+}
+
+/// Chooses a move for 'cell' at random, but takes a corner if it can.
+/// This tries empty cells in random order until it finds a valid move,
+/// which is much quicker than finding every valid move. This returns
+/// an invalid movement if 'cell' has no valid move.
+fn choose_random_move(board: &Board, cell: Cell, random: &mut Random) -> Movement {
+    let right = board.get_width() - 1;
+    let bottom = board.get_height() - 1;
+    let corners = [
+        Loc::new(0, 0),
+        Loc::new(right, 0),
+        Loc::new(0, bottom),
+        Loc::new(right, bottom),
+    ];
+
+    for corner in corners {
+        let mv = Movement::new(board, corner, cell);
+        if mv.is_valid() {
+            return mv;
+        }
+    }
+
+    let mut empty: Vec<Loc> = board
+        .locations()
+        .filter(|&loc| board[loc] == Cell::Empty)
+        .collect();
+
+    while !empty.is_empty() {
+        let loc = empty.swap_remove(random.below(empty.len()));
+        let mv = Movement::new(board, loc, cell);
+        if mv.is_valid() {
+            return mv;
+        }
+    }
+
+    Movement::default()
+}
+
+fn find_winner(black: usize, white: usize) -> Cell {
+    match black.cmp(&white) {
+        std::cmp::Ordering::Greater => Cell::Black,
+        std::cmp::Ordering::Less => Cell::White,
+        std::cmp::Ordering::Equal => Cell::Empty,
+    }
+}
+
+/// Records the result of a game played out from 'leaf' in each
+/// node from there back up to the root.
+fn propagate(tree: &mut [Node], leaf: usize, winner: Cell) {
+    let mut index = Some(leaf);
+
+    while let Some(i) = index {
+        let node = &mut tree[i];
+        node.visits += 1;
+
+        // The player who moved into this node is the one not to move in it.
+        if winner == node.cell.flipped() {
+            node.wins += 1.0;
+        } else if winner == Cell::Empty {
+            node.wins += 0.5;
+        }
+
+        index = node.parent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    /// Checks that 'mv' is a valid move for 'cell' on 'board'.
+    fn assert_valid(board: &Board, cell: Cell, mv: &Movement) {
+        let loc = mv.get_loc().expect("a move was found");
+        assert!(Movement::new(board, loc, cell).is_valid(), "{}", loc);
+    }
+
+    #[test]
+    fn moves_are_valid_on_any_size_of_board() {
+        for (width, height) in [(8, 8), (10, 8), (6, 6)] {
+            for seed in 0..3 {
+                let mut game = RandomGame::new(width, height, seed);
+                game.play_until(width * height / 2);
+
+                let (board, cell) = (game.get_board(), game.get_cell());
+                if board.has_valid_move(cell) {
+                    assert_valid(board, cell, &MonteCarlo::new(200).find_move(board, cell));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn forced_wins_are_taken() {
+        // Black can take every white disc by moving at f4.
+        let transcript: Transcript = "d3c3b3d2e1d6d7e3".parse().unwrap();
+        let game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        let board = game.to_board();
+        assert!(board.count_valid_moves(Cell::Black) > 1);
+
+        let mv = MonteCarlo::new(500).find_move(board, Cell::Black);
+        assert_eq!(mv.get_loc(), Some(Loc::new(5, 3)));
+    }
+
+    #[test]
+    fn players_with_no_move_get_an_invalid_one() {
+        let transcript: Transcript = "d3c3b3d2e1d6d7e3f4".parse().unwrap();
+        let game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        let mv = MonteCarlo::new(100).find_move(game.to_board(), Cell::White);
+        assert!(!mv.is_valid());
+    }
+
+    #[test]
+    fn removing_the_time_limit_restores_the_game_count() {
+        let board = Board::new(8, 8);
+        let mut search = MonteCarlo::with_default_time();
+        search.set_think_time(Some(Duration::from_millis(20)));
+
+        let start = Instant::now();
+        assert_valid(&board, Cell::Black, &search.find_move(&board, Cell::Black));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // This plays out DEFAULT_ITERATIONS games, rather than
+        // running on with no limit at all.
+        search.set_think_time(None);
+        assert_valid(&board, Cell::Black, &search.find_move(&board, Cell::Black));
+    }
+
+    #[test]
+    fn canceled_searches_stop() {
+        let mut search = MonteCarlo::new(usize::MAX);
+        search.set_cancel_flag(Arc::new(AtomicBool::new(true)));
+
+        let board = Board::new(8, 8);
+        let start = Instant::now();
+        search.find_move(&board, Cell::Black);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -14,7 +14,12 @@ impl Movement {
     /// invalid, if the location is not empty or would flip no other cells.
     pub fn new(board: &Board, loc: Loc, cell: Cell) -> Movement {
         if board[loc] == Cell::Empty {
-            let mut flips: Vec<_> = Movement::find_flippable_around(board, loc, cell)
+            let flippable = match board.get_bitboard() {
+                Some(bits) => bits.flipped_locations(cell, loc),
+                None => Movement::find_flippable_around(board, loc, cell).collect(),
+            };
+            let mut flips: Vec<_> = flippable
+                .into_iter()
                 .map(|loc| CellChange::new(cell, loc))
                 .collect();
             if !flips.is_empty() {
@@ -31,6 +36,18 @@ impl Movement {
         !self.flips.is_empty()
     }
 
+    /// The location where this move places its disc; this is
+    /// None for an invalid move.
+    pub fn get_loc(&self) -> Option<Loc> {
+        self.flips.first().map(|change| change.loc)
+    }
+
+    /// The number of discs this move flips, not counting
+    /// the disc it places.
+    pub fn get_flip_count(&self) -> usize {
+        self.flips.len().saturating_sub(1)
+    }
+
     /// Plays a move; it flips the cells indicated by the move. If this move
     /// is invalid, this method does nothing. It removes the flip that it
     /// performs, so that the movement may become invalid.
@@ -46,6 +63,14 @@ impl Movement {
         }
     }
 
+    /// Plays the whole move at once, flipping every cell it indicates. Unlike
+    /// play_one(), this leaves the movement itself unchanged.
+    pub fn play(&self, board: &mut Board) {
+        for &change in &self.flips {
+            board.apply_change(change);
+        }
+    }
+
     /// Returns a score for this move; moves with higher scores
     /// are preferred. This returns MIN for invalid moves.
     pub fn get_score(&self, board: &Board) -> usize {
@@ -126,12 +151,98 @@ impl Movement {
 /// and the location to put it.
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct CellChange {
+    /// The cell to write.
     pub cell: Cell,
+    /// Where to write it.
     pub loc: Loc,
 }
 
 impl CellChange {
+    /// Creates a change that puts 'cell' at 'loc'.
     pub fn new(cell: Cell, loc: Loc) -> CellChange {
         CellChange { cell, loc }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes an 8x8 board whose top row is given by 'row', using 'X'
+    /// for black, 'O' for white and '.' for empty; the rest of the
+    /// board is as it is at the start.
+    fn board_with_top_row(row: &str) -> Board {
+        let mut board = Board::new(8, 8);
+        for (x, c) in row.chars().enumerate() {
+            let cell = match c {
+                'X' => Cell::Black,
+                'O' => Cell::White,
+                _ => Cell::Empty,
+            };
+            board.apply_change(CellChange::new(cell, Loc::new(x, 0)));
+        }
+        board
+    }
+
+    /// Finds what 'cell' would flip at 'start' looking left
+    /// along the top row.
+    fn flippable_leftwards(row: &str, start: usize, cell: Cell) -> Vec<Loc> {
+        let board = board_with_top_row(row);
+        let candidates = board.cells_from(Loc::new(start, 0), -1, 0).skip(1);
+        Movement::find_flippable(&board, cell, candidates)
+    }
+
+    fn top_row(xs: &[usize]) -> Vec<Loc> {
+        xs.iter().map(|&x| Loc::new(x, 0)).collect()
+    }
+
+    #[test]
+    fn find_flippable_takes_a_line_ending_in_the_players_disc() {
+        let flips = flippable_leftwards("XOOO....", 4, Cell::Black);
+        assert_eq!(flips, top_row(&[3, 2, 1]));
+
+        let flips = flippable_leftwards("OXX.....", 3, Cell::White);
+        assert_eq!(flips, top_row(&[2, 1]));
+    }
+
+    #[test]
+    fn find_flippable_stops_at_the_first_of_the_players_discs() {
+        let flips = flippable_leftwards("XOXO....", 4, Cell::Black);
+        assert_eq!(flips, top_row(&[3]));
+    }
+
+    #[test]
+    fn find_flippable_needs_the_players_disc_at_the_end() {
+        assert!(flippable_leftwards(".OOO....", 4, Cell::Black).is_empty());
+        assert!(flippable_leftwards("X.OO....", 4, Cell::Black).is_empty());
+        assert!(flippable_leftwards("OOOO....", 4, Cell::Black).is_empty());
+    }
+
+    #[test]
+    fn find_flippable_needs_an_opponents_disc_between() {
+        assert!(flippable_leftwards("...X....", 4, Cell::Black).is_empty());
+        assert!(flippable_leftwards("........", 4, Cell::Black).is_empty());
+        assert!(flippable_leftwards("........", 0, Cell::Black).is_empty());
+    }
+
+    #[test]
+    fn general_moves_agree_with_bitboard_moves() {
+        let transcript: Transcript = "f5d6c3d3c4f4f6f3e6e7".parse().unwrap();
+        let game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        let board = game.to_board();
+        let bits = board.get_bitboard().unwrap();
+
+        for cell in [Cell::Black, Cell::White] {
+            for loc in board.locations().filter(|&loc| board[loc] == Cell::Empty) {
+                let general: Vec<_> = Movement::find_flippable_around(board, loc, cell).collect();
+                assert_eq!(
+                    general,
+                    bits.flipped_locations(cell, loc),
+                    "{} at {}",
+                    cell.name(),
+                    loc
+                );
+            }
+        }
+    }
+}
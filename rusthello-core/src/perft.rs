@@ -0,0 +1,113 @@
+use super::*;
+
+impl Board {
+    /// Counts the positions that can be reached by playing 'depth' moves
+    /// from this one, with 'cell' to move first. A player with no valid
+    /// move must pass, and the pass counts as one of the moves; a game
+    /// that ends early counts as a single position.
+    ///
+    /// This is for checking that moves are found correctly, by comparing
+    /// the counts with those known for the standard board.
+    pub fn perft(&self, cell: Cell, depth: u32) -> u64 {
+        match self.get_bitboard() {
+            Some(bits) => perft_bits(bits, cell, depth),
+            None => perft_board(self, cell, depth),
+        }
+    }
+}
+
+/// Counts positions for any board, using Movement to find the moves.
+fn perft_board(board: &Board, cell: Cell, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = board.find_valid_moves(cell);
+    if moves.is_empty() {
+        return if board.has_valid_move(cell.flipped()) {
+            perft_board(board, cell.flipped(), depth - 1)
+        } else {
+            1
+        };
+    }
+
+    let mut count = 0;
+    for mv in &moves {
+        let mut next = board.clone();
+        mv.play(&mut next);
+        count += perft_board(&next, cell.flipped(), depth - 1);
+    }
+    count
+}
+
+/// Counts positions on an 8x8 board, using its bitboard.
+fn perft_bits(bits: &Bitboard, cell: Cell, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = bits.valid_moves(cell);
+    if moves == 0 {
+        return if bits.valid_moves(cell.flipped()) != 0 {
+            perft_bits(bits, cell.flipped(), depth - 1)
+        } else {
+            1
+        };
+    }
+
+    if depth == 1 {
+        return u64::from(moves.count_ones());
+    }
+
+    Bitboard::locations(moves)
+        .map(|loc| perft_bits(&bits.play(cell, loc), cell.flipped(), depth - 1))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitboards_agree_with_general_boards() {
+        // tests/perft.rs checks the bitboard against the known counts,
+        // so this checks the general code against those counts too.
+        let board = Board::new(8, 8);
+        let bits = board.get_bitboard().unwrap();
+
+        for depth in 0..=6 {
+            assert_eq!(
+                perft_bits(bits, Cell::Black, depth),
+                perft_board(&board, Cell::Black, depth)
+            );
+        }
+    }
+
+    #[test]
+    fn depth_zero_counts_only_the_position() {
+        let board = Board::new(6, 6);
+        assert_eq!(board.perft(Cell::Black, 0), 1);
+    }
+
+    #[test]
+    fn smaller_boards_have_four_first_moves() {
+        for size in [4, 6, 10] {
+            let board = Board::new(size, size);
+            assert_eq!(board.perft(Cell::Black, 1), 4);
+            assert_eq!(board.perft(Cell::Black, 2), 12);
+        }
+    }
+
+    #[test]
+    fn finished_games_count_once() {
+        // Black takes every white disc in nine moves.
+        let transcript: Transcript = "d3c3b3d2e1d6d7e3f4".parse().unwrap();
+        let game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+        let board = game.to_board();
+
+        assert!(board.is_game_over());
+        assert_eq!(board.count_cells(Cell::White), 0);
+        assert_eq!(board.perft(Cell::White, 3), 1);
+        assert_eq!(perft_board(board, Cell::White, 3), 1);
+    }
+}
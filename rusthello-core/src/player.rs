@@ -0,0 +1,64 @@
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::*;
+
+/// Decides who makes the moves for one side of the game.
+#[derive(Clone)]
+pub enum Player {
+    /// A person, who makes moves through the user interface.
+    Human,
+    /// The computer, which uses a search to choose its moves.
+    Computer(Search),
+    /// The computer, using Monte Carlo tree search instead.
+    MonteCarlo(MonteCarlo),
+}
+
+impl Player {
+    /// True if this player's moves come from a person.
+    pub fn is_human(&self) -> bool {
+        matches!(self, Player::Human)
+    }
+
+    /// Chooses a move for 'cell' on the board given. A human player
+    /// can't be asked, so this returns an invalid movement for them.
+    pub fn choose_move(&self, board: &Board, cell: Cell) -> Movement {
+        match self {
+            Player::Human => Movement::default(),
+            Player::Computer(search) => search.find_move(board, cell),
+            Player::MonteCarlo(monte_carlo) => monte_carlo.find_move(board, cell),
+        }
+    }
+
+    /// Sets how long a computer player may take to choose each move;
+    /// see Search::set_think_time().
+    pub fn set_think_time(&mut self, think_time: Option<Duration>) {
+        match self {
+            Player::Human => {}
+            Player::Computer(search) => search.set_think_time(think_time),
+            Player::MonteCarlo(monte_carlo) => monte_carlo.set_think_time(think_time),
+        }
+    }
+
+    /// Sets a flag that stops a computer player's search early;
+    /// see Search::set_cancel_flag().
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>) {
+        match self {
+            Player::Human => {}
+            Player::Computer(search) => search.set_cancel_flag(cancel),
+            Player::MonteCarlo(monte_carlo) => monte_carlo.set_cancel_flag(cancel),
+        }
+    }
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Player::Human => write!(f, "Human"),
+            Player::Computer(search) => write!(f, "{}", search),
+            Player::MonteCarlo(monte_carlo) => write!(f, "{}", monte_carlo),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A small, fast pseudo-random number generator (xorshift64*); it is
+/// good enough to vary the computer's play, but not for anything that
+/// needs to be unpredictable.
+#[derive(Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Creates a generator that produces the same numbers
+    /// every time for a given seed.
+    pub fn new(seed: u64) -> Random {
+        // xorshift gets stuck at zero, so we mix the seed
+        // and make sure we don't start there.
+        let state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        Random { state }
+    }
+
+    /// Creates a generator seeded from the system clock, so
+    /// it produces different numbers each time.
+    pub fn from_time() -> Random {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Random::new(nanos)
+    }
+
+    /// Returns the next number in the sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns a number from 0 up to (but not including) 'limit',
+    /// which must not be zero.
+    pub fn below(&mut self, limit: usize) -> usize {
+        (((self.next_u64() >> 32) * limit as u64) >> 32) as usize
+    }
+}
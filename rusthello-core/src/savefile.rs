@@ -0,0 +1,340 @@
+use std::io::*;
+
+use super::*;
+
+/// The first line of every saved game; the number is the
+/// version of the format.
+const HEADER: &str = "rusthello-game 2";
+
+/// The first line of games saved before the format recorded
+/// resignations; these can still be loaded.
+const HEADER_1: &str = "rusthello-game 1";
+
+impl Board {
+    /// Writes the board as text: a line giving its size, then one line
+    /// per row, with 'X' for black, 'O' for white and '.' for empty cells.
+    pub fn save(&self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "size {} {}", self.get_width(), self.get_height())?;
+
+        for y in 0..self.get_height() {
+            let row: String = (0..self.get_width())
+                .map(|x| match self[Loc::new(x, y)] {
+                    Cell::Empty => '.',
+                    Cell::Black => 'X',
+                    Cell::White => 'O',
+                })
+                .collect();
+            writeln!(out, "{}", row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a board written by save().
+    pub fn load(input: &mut dyn BufRead) -> Result<Board> {
+        let size = read_field(input, "size")?;
+        let mut size = size.split_whitespace().map(|n| n.parse::<usize>());
+        let (width, height) = match (size.next(), size.next(), size.next()) {
+            (Some(Ok(width)), Some(Ok(height)), None)
+                if valid_size(width) && valid_size(height) =>
+            {
+                (width, height)
+            }
+            _ => return Err(invalid("the board size is not valid")),
+        };
+
+        let mut board = Board::new(width, height);
+
+        for y in 0..height {
+            let row = read_line(input)?;
+            if row.chars().count() != width {
+                return Err(invalid("a row of the board is the wrong length"));
+            }
+
+            for (x, c) in row.chars().enumerate() {
+                let cell = match c {
+                    '.' => Cell::Empty,
+                    'X' => Cell::Black,
+                    'O' => Cell::White,
+                    _ => return Err(invalid("the board contains an unknown cell")),
+                };
+                board.apply_change(CellChange::new(cell, Loc::new(x, y)));
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+impl Game {
+    /// Writes the game as text: the board, whose turn it is, who resigned,
+    /// if anyone did, and the moves played so far. Any ongoing movement
+    /// is completed first, so that the board saved is the one the moves
+    /// lead to.
+    pub fn save(&mut self, out: &mut dyn Write) -> Result<()> {
+        self.finish_movement();
+
+        writeln!(out, "{}", HEADER)?;
+        self.board.save(out)?;
+        writeln!(out, "next {}", cell_name(self.next_move))?;
+        writeln!(
+            out,
+            "resigned {}",
+            cell_name(self.resigned.unwrap_or(Cell::Empty))
+        )?;
+        writeln!(out, "moves {}", self.to_transcript())?;
+        Ok(())
+    }
+
+    /// Reads a game written by save(), or by the earlier version that
+    /// did not record resignations. This replays the saved moves, so
+    /// they can be undone, and checks that they lead to the saved board.
+    pub fn load(input: &mut dyn BufRead) -> Result<Game> {
+        let header = read_line(input)?;
+        if header != HEADER && header != HEADER_1 {
+            return Err(invalid("this is not a saved game"));
+        }
+
+        let board = Board::load(input)?;
+        let next_move = read_field(input, "next")?;
+        let resigned = if header == HEADER_1 {
+            String::from("nobody")
+        } else {
+            read_field(input, "resigned")?
+        };
+        let resigned = match resigned.as_str() {
+            "nobody" => None,
+            "black" => Some(Cell::Black),
+            "white" => Some(Cell::White),
+            _ => return Err(invalid("the player who resigned is not valid")),
+        };
+        let moves: Transcript = read_field(input, "moves")?
+            .parse()
+            .map_err(|err| invalid(&format!("{}", err)))?;
+
+        let fresh = Board::new(board.get_width(), board.get_height());
+        let mut game =
+            Game::from_transcript(fresh, &moves).map_err(|err| invalid(&format!("{}", err)))?;
+
+        let same_board = board.locations().all(|loc| board[loc] == game.board[loc]);
+        if !same_board || cell_name(game.next_move) != next_move {
+            return Err(invalid("the saved board does not match the saved moves"));
+        }
+
+        if let Some(cell) = resigned {
+            if !game.resign(cell) {
+                return Err(invalid("a player resigned after the game was over"));
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+fn valid_size(size: usize) -> bool {
+    (Board::MIN_SIZE..=Board::MAX_SIZE).contains(&size)
+}
+
+fn cell_name(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Empty => "nobody",
+        Cell::Black => "black",
+        Cell::White => "white",
+    }
+}
+
+/// Reads a line that starts with 'name' and returns the rest of it.
+fn read_field(input: &mut dyn BufRead, name: &str) -> Result<String> {
+    let line = read_line(input)?;
+    let mut parts = line.splitn(2, ' ');
+
+    if parts.next() == Some(name) {
+        Ok(parts.next().unwrap_or("").trim().to_string())
+    } else {
+        Err(invalid(&format!("expected '{}'", name)))
+    }
+}
+
+/// Reads a line, without its line ending; this fails at
+/// the end of the file.
+fn read_line(input: &mut dyn BufRead) -> Result<String> {
+    let mut line = String::new();
+
+    if input.read_line(&mut line)? == 0 {
+        Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "the saved game is incomplete",
+        ))
+    } else {
+        Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A game ten moves in, with black to move.
+    fn game_in_progress() -> Game {
+        let transcript: Transcript = "f5d6c3d3c4f4f6f3e6e7".parse().unwrap();
+        Game::from_transcript(Board::new(8, 8), &transcript).unwrap()
+    }
+
+    fn saved(game: &mut Game) -> String {
+        let mut out = Vec::new();
+        game.save(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn load(text: &str) -> Result<Game> {
+        Game::load(&mut text.as_bytes())
+    }
+
+    fn same_board(a: &Board, b: &Board) -> bool {
+        a.get_width() == b.get_width()
+            && a.get_height() == b.get_height()
+            && a.locations().all(|loc| a[loc] == b[loc])
+    }
+
+    #[test]
+    fn saved_games_load_unchanged() {
+        let mut game = game_in_progress();
+        let mut loaded = load(&saved(&mut game)).unwrap();
+
+        assert!(same_board(loaded.to_board(), game.to_board()));
+        assert_eq!(loaded.get_next_move(), Cell::Black);
+        assert_eq!(loaded.to_transcript(), game.to_transcript());
+        assert_eq!(saved(&mut loaded), saved(&mut game));
+    }
+
+    #[test]
+    fn loaded_games_can_be_undone() {
+        let mut game = game_in_progress();
+        let mut loaded = load(&saved(&mut game)).unwrap();
+
+        while game.undo() {
+            assert!(loaded.undo());
+            assert!(same_board(loaded.to_board(), game.to_board()));
+            assert_eq!(loaded.get_next_move(), game.get_next_move());
+        }
+        assert!(!loaded.undo());
+    }
+
+    #[test]
+    fn games_on_other_sizes_load_unchanged() {
+        let mut game = Game::new(Board::new(10, 6));
+        for _ in 0..6 {
+            let cell = game.get_next_move();
+            let mv = game.to_board().find_valid_moves(cell).remove(0);
+            assert!(game.begin_movement(mv));
+            game.finish_movement();
+        }
+
+        let mut loaded = load(&saved(&mut game)).unwrap();
+        assert!(same_board(loaded.to_board(), game.to_board()));
+        assert_eq!(loaded.get_next_move(), game.get_next_move());
+        assert_eq!(saved(&mut loaded), saved(&mut game));
+    }
+
+    #[test]
+    fn movements_are_finished_before_saving() {
+        let mut game = game_in_progress();
+        let mv = game.to_board().find_valid_moves(Cell::Black).remove(0);
+        assert!(game.begin_movement(mv));
+
+        let loaded = load(&saved(&mut game)).unwrap();
+        assert_eq!(loaded.get_next_move(), Cell::White);
+        assert_eq!(loaded.to_transcript().get_moves().len(), 11);
+        assert!(same_board(loaded.to_board(), game.to_board()));
+    }
+
+    #[test]
+    fn resignations_are_saved() {
+        let mut game = game_in_progress();
+        assert!(game.resign(Cell::White));
+
+        let mut loaded = load(&saved(&mut game)).unwrap();
+        assert_eq!(loaded.get_resigned(), Some(Cell::White));
+        assert!(loaded.is_over());
+
+        // Undoing takes back the resignation, then the moves.
+        assert!(loaded.undo());
+        assert_eq!(loaded.get_resigned(), None);
+        assert_eq!(loaded.get_next_move(), Cell::Black);
+        assert!(same_board(loaded.to_board(), game.to_board()));
+
+        let text = saved(&mut game);
+        let err = load(&text.replace("resigned white", "resigned grey"))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn games_saved_before_resignations_still_load() {
+        let mut game = game_in_progress();
+        let text = saved(&mut game)
+            .replace(HEADER, HEADER_1)
+            .replace("resigned nobody\n", "");
+
+        let mut loaded = load(&text).unwrap();
+        assert!(same_board(loaded.to_board(), game.to_board()));
+        assert_eq!(loaded.get_resigned(), None);
+        assert_eq!(saved(&mut loaded), saved(&mut game));
+
+        // The new format must say who resigned.
+        let text = saved(&mut game).replace("resigned nobody\n", "");
+        let err = load(&text).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn boards_that_do_not_match_the_moves_are_rejected() {
+        let text = saved(&mut game_in_progress());
+
+        // Swap one black disc for a white one.
+        let changed = text.replacen('X', "O", 1);
+        assert_ne!(changed, text);
+        let err = load(&changed).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // Leave out the last move, so the board is one move ahead.
+        let fewer = text.replace("f3e6e7\n", "f3e6\n");
+        assert_ne!(fewer, text);
+        let err = load(&fewer).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let wrong_turn = text.replace("next black", "next white");
+        assert_ne!(wrong_turn, text);
+        let err = load(&wrong_turn).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn damaged_games_do_not_load() {
+        let text = saved(&mut game_in_progress());
+
+        let first_lines: String = text
+            .lines()
+            .take(5)
+            .map(|line| line.to_string() + "\n")
+            .collect();
+        let err = load(&first_lines).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let err = load(&text.replace(HEADER, "rusthello-game 99"))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = load(&text.replace("size 8 8", "size 8 80")).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = load(&text.replace("moves f5", "moves z5")).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,579 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use super::*;
+
+/// A score larger than any evaluation can produce; used
+/// as the initial alpha-beta window.
+const INFINITY: i32 = 1_000_000;
+
+/// The score for a won game, before adding the disc difference. This
+/// keeps any win ahead of any position that is merely good.
+const WIN: i32 = 10_000;
+
+/// The number of empty cells at which a search switches to
+/// solving the endgame exactly, unless told otherwise.
+pub const DEFAULT_ENDGAME_EMPTIES: usize = 12;
+
+/// Search looks ahead some number of moves using negamax with
+/// alpha-beta pruning, and picks the move that gives the best
+/// outcome assuming the opponent plays as well as it can. Near
+/// the end of the game, it solves the endgame exactly instead.
+///
+/// The search deepens one move at a time, so it can stop when its
+/// time is up and still have a move to offer.
+///
+/// A search made for a difficulty level may not search at all; the
+/// weakest levels choose their moves more simply.
+///
+/// The search keeps its transposition table from move to move, and
+/// copies of a search share it.
+#[derive(Clone)]
+pub struct Search {
+    depth: usize,
+    endgame_empties: usize,
+    think_time: Option<Duration>,
+    cancel: Option<Arc<AtomicBool>>,
+    level: Option<Level>,
+    evaluation: Evaluation,
+    book: Option<Arc<OpeningBook>>,
+    table: Arc<Mutex<TranspositionTable>>,
+}
+
+/// Selects how a search judges the positions at the end of the
+/// moves it looks at.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Evaluation {
+    /// Board::evaluate_simply(), which is quick but crude.
+    Simple,
+    /// Board::evaluate(), which considers much more.
+    Full,
+}
+
+/// What a search keeps track of as it goes: the positions it has
+/// seen, and when it must stop. The endgame solver uses this too.
+pub(crate) struct SearchState<'a> {
+    pub(crate) table: &'a mut TranspositionTable,
+    pub(crate) deadline: Option<Instant>,
+    pub(crate) cancel: Option<&'a AtomicBool>,
+}
+
+impl SearchState<'_> {
+    /// True once the search has used up its time, or been canceled.
+    pub(crate) fn must_stop(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+            || self
+                .cancel
+                .is_some_and(|c| c.load(atomic::Ordering::Relaxed))
+    }
+}
+
+impl Search {
+    /// Creates a search that looks ahead 'depth' moves; a depth of
+    /// 1 considers only the immediate result of each move.
+    pub fn new(depth: usize) -> Search {
+        Search {
+            depth: depth.max(1),
+            endgame_empties: DEFAULT_ENDGAME_EMPTIES,
+            think_time: None,
+            cancel: None,
+            level: None,
+            evaluation: Evaluation::Full,
+            book: None,
+            table: Arc::default(),
+        }
+    }
+
+    /// Creates a search that plays at the level given.
+    pub fn for_level(level: Level) -> Search {
+        let mut search = Search::new(1);
+        search.set_level(level);
+        search
+    }
+
+    /// Makes the search play at the level given; this sets how far
+    /// ahead it looks and when it solves the endgame, but not how
+    /// long it may think.
+    pub fn set_level(&mut self, level: Level) {
+        let (depth, evaluation, endgame_empties) = match level {
+            Level::Beginner | Level::Easy => (1, Evaluation::Simple, 0),
+            Level::Medium => (2, Evaluation::Simple, 0),
+            Level::Hard => (4, Evaluation::Full, DEFAULT_ENDGAME_EMPTIES),
+            Level::Expert => (6, Evaluation::Full, 16),
+        };
+
+        self.level = Some(level);
+        self.depth = depth;
+        self.evaluation = evaluation;
+        self.endgame_empties = endgame_empties;
+
+        // What the old level learned was scored its own way,
+        // so we start again with an empty table.
+        self.table = Arc::default();
+    }
+
+    /// The level the search plays at; this is None for a
+    /// search made with new().
+    pub fn get_level(&self) -> Option<Level> {
+        self.level
+    }
+
+    /// Sets the number of empty cells at which the search stops
+    /// estimating and solves the endgame exactly; zero means never.
+    pub fn set_endgame_empties(&mut self, empties: usize) {
+        self.endgame_empties = empties;
+    }
+
+    /// Sets how long the search may take to choose each move. With a
+    /// time limit, the search looks as far ahead as it can in that time
+    /// rather than to a fixed depth; with None, it takes as long as its
+    /// depth requires.
+    pub fn set_think_time(&mut self, think_time: Option<Duration>) {
+        self.think_time = think_time;
+    }
+
+    /// Gives the search an opening book; when the book has moves for
+    /// a position, the search plays one of them instead of searching.
+    /// The weakest levels ignore the book.
+    pub fn set_book(&mut self, book: Arc<OpeningBook>) {
+        self.book = Some(book);
+    }
+
+    /// Sets a flag that stops the search early when it becomes true;
+    /// the move found then should not be used.
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = Some(cancel);
+    }
+
+    /// Returns the best move for 'cell' on the board given. This returns an
+    /// invalid movement if 'cell' has no valid moves.
+    pub fn find_move(&self, board: &Board, cell: Cell) -> Movement {
+        match self.level {
+            Some(Level::Beginner) => {
+                let mut moves = board.find_valid_moves(cell);
+                if moves.is_empty() {
+                    return Movement::default();
+                }
+                let index = Random::from_time().below(moves.len());
+                return moves.swap_remove(index);
+            }
+            Some(Level::Easy) => {
+                // The moves are sorted so the one that scores
+                // best right now comes first.
+                let moves = board.find_valid_moves(cell);
+                return moves.into_iter().next().unwrap_or_default();
+            }
+            _ => {}
+        }
+
+        if let Some(mv) = self.book.as_ref().and_then(|b| b.choose_move(board, cell)) {
+            return mv;
+        }
+
+        let start = Instant::now();
+        let deadline = self.think_time.and_then(|t| start.checked_add(t));
+        // A search canceled a moment ago may still hold the table,
+        // but it will give it up very soon.
+        let mut table = self.table.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut state = SearchState {
+            table: &mut table,
+            deadline,
+            cancel: self.cancel.as_deref(),
+        };
+
+        // If the solver runs out of time, we fall back on the search
+        // below, which always finishes its first pass and so still
+        // finds a move. If the solver is canceled, so is the search,
+        // which gives up at once; its move is not used.
+        if board.count_cells(Cell::Empty) <= self.endgame_empties {
+            if let Some(solution) = board.solve_endgame_within(cell, &mut state) {
+                return solution.best_move;
+            }
+        }
+
+        // Looking further ahead than the number of empty cells can't
+        // tell us any more; the game is over by then.
+        let max_depth = match self.think_time {
+            Some(_) => board.count_cells(Cell::Empty).max(1),
+            None => self.depth,
+        };
+
+        let mut best_move = Movement::default();
+        state.deadline = None;
+
+        for depth in 1..=max_depth {
+            match self.search_root(&mut state, board, cell, depth, &best_move) {
+                Some(mv) => best_move = mv,
+                None => break,
+            }
+
+            // The first search always finishes unless canceled, so we
+            // have a move; after that, we may run out of time.
+            state.deadline = deadline;
+            if state.must_stop() {
+                break;
+            }
+        }
+
+        best_move
+    }
+
+    /// Searches each of 'cell's moves 'depth' moves deep, and returns the
+    /// best. 'first' is searched first, as it is probably the best. This
+    /// returns None if the search must stop before it is done.
+    fn search_root(
+        &self,
+        state: &mut SearchState,
+        board: &Board,
+        cell: Cell,
+        depth: usize,
+        first: &Movement,
+    ) -> Option<Movement> {
+        let mut moves = board.find_valid_moves(cell);
+        if let Some(first) = moves.iter().position(|mv| mv.get_loc() == first.get_loc()) {
+            moves[..=first].rotate_right(1);
+        }
+
+        let mut best_move = Movement::default();
+        let mut alpha = -INFINITY;
+
+        for mv in moves {
+            let mut next = board.clone();
+            mv.play(&mut next);
+
+            let score =
+                -self.negamax(state, &next, cell.flipped(), depth - 1, -INFINITY, -alpha)?;
+            if score > alpha || !best_move.is_valid() {
+                alpha = score;
+                best_move = mv;
+            }
+        }
+
+        Some(best_move)
+    }
+
+    /// Scores the board from the point of view of 'cell', who is to move. Higher
+    /// scores are better for 'cell'; the result is bounded by alpha and beta.
+    /// What is learned is kept in the state's table, and positions already
+    /// found there need not be searched again. This returns None if the
+    /// search must stop.
+    fn negamax(
+        &self,
+        state: &mut SearchState,
+        board: &Board,
+        cell: Cell,
+        depth: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        if board.is_game_over() {
+            return Some(final_score(board, cell));
+        }
+
+        if depth == 0 {
+            return Some(match self.evaluation {
+                Evaluation::Simple => board.evaluate_simply(cell),
+                Evaluation::Full => board.evaluate(cell),
+            });
+        }
+
+        if state.must_stop() {
+            return None;
+        }
+
+        let mut moves = board.find_valid_moves(cell);
+
+        if moves.is_empty() {
+            // 'cell' must pass, so the opponent moves again.
+            let score = self.negamax(state, board, cell.flipped(), depth - 1, -beta, -alpha)?;
+            return Some(-score);
+        }
+
+        let hash = board.get_hash();
+        if let Some(entry) = state.table.probe(hash, cell) {
+            if let Some(score) = entry.get_cutoff(depth as u32, alpha, beta) {
+                return Some(score);
+            }
+
+            // The move that was best before is likely best again,
+            // so we try it first.
+            if let Some(first) = moves.iter().position(|mv| mv.get_loc() == entry.best_move) {
+                moves[..=first].rotate_right(1);
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+
+        for mv in moves {
+            let mut next = board.clone();
+            mv.play(&mut next);
+
+            let score = -self.negamax(state, &next, cell.flipped(), depth - 1, -beta, -alpha)?;
+            if score > best_score {
+                best_score = score;
+                best_move = mv.get_loc();
+            }
+            if score >= beta {
+                break;
+            }
+            alpha = alpha.max(score);
+        }
+
+        let entry = Entry {
+            depth: depth as u32,
+            bound: Bound::for_score(best_score, original_alpha, beta),
+            score: best_score,
+            best_move,
+        };
+        state.table.store(hash, cell, entry);
+
+        Some(best_score)
+    }
+}
+
+impl fmt::Display for Search {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level {
+            Some(level) => write!(f, "{}", level),
+            None => write!(f, "Depth {}", self.depth),
+        }
+    }
+}
+
+/// Scores a finished game for 'cell'; wins score above any
+/// evaluation, and losses below.
+fn final_score(board: &Board, cell: Cell) -> i32 {
+    score_for_difference(disc_difference(board, cell))
+}
+
+/// Converts the final disc difference of a game to a score
+/// like those the search gives.
+pub(crate) fn score_for_difference(diff: i32) -> i32 {
+    match diff.cmp(&0) {
+        Ordering::Greater => WIN + diff,
+        Ordering::Less => -WIN + diff,
+        Ordering::Equal => 0,
+    }
+}
+
+/// Converts a score from score_for_difference() back
+/// to the disc difference.
+pub(crate) fn difference_for_score(score: i32) -> i32 {
+    match score.cmp(&0) {
+        Ordering::Greater => score - WIN,
+        Ordering::Less => score + WIN,
+        Ordering::Equal => 0,
+    }
+}
+
+/// The number of discs 'cell' has, less the number its opponent has.
+fn disc_difference(board: &Board, cell: Cell) -> i32 {
+    board.count_cells(cell) as i32 - board.count_cells(cell.flipped()) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::testing::*;
+
+    /// Positions part way through random games, on the bitboard and on
+    /// a smaller board, with the player to move. The 8x8 boards have
+    /// 'empties' empty cells, and the others as large a share of theirs.
+    fn random_positions(empties: usize) -> Vec<(Board, Cell)> {
+        let mut positions = Vec::new();
+        for size in [8, 6] {
+            for seed in 0..4 {
+                let mut game = RandomGame::new(size, size, seed);
+                game.play_until(empties * size * size / 64);
+                if !game.is_over() {
+                    positions.push((game.get_board().clone(), game.get_cell()));
+                }
+            }
+        }
+        positions
+    }
+
+    /// Scores the board for 'cell' as negamax() should, but by trying
+    /// every line of play 'depth' moves deep, without any pruning.
+    fn minimax(board: &Board, cell: Cell, depth: usize) -> i32 {
+        if board.is_game_over() {
+            return final_score(board, cell);
+        }
+        if depth == 0 {
+            return board.evaluate(cell);
+        }
+
+        let moves = board.find_valid_moves(cell);
+        if moves.is_empty() {
+            return -minimax(board, cell.flipped(), depth - 1);
+        }
+
+        moves
+            .iter()
+            .map(|mv| {
+                let mut next = board.clone();
+                mv.play(&mut next);
+                -minimax(&next, cell.flipped(), depth - 1)
+            })
+            .max()
+            .unwrap()
+    }
+
+    /// Runs negamax() with the table and window given.
+    fn negamax(
+        search: &Search,
+        table: &mut TranspositionTable,
+        board: &Board,
+        cell: Cell,
+        depth: usize,
+        (alpha, beta): (i32, i32),
+    ) -> i32 {
+        let mut state = SearchState {
+            table,
+            deadline: None,
+            cancel: None,
+        };
+        search
+            .negamax(&mut state, board, cell, depth, alpha, beta)
+            .unwrap()
+    }
+
+    #[test]
+    fn negamax_agrees_with_minimax() {
+        let search = Search::new(3);
+
+        for (board, cell) in random_positions(40) {
+            let expected = minimax(&board, cell, 3);
+            let mut fresh = TranspositionTable::default();
+            let window = (-INFINITY, INFINITY);
+            assert_eq!(
+                negamax(&search, &mut fresh, &board, cell, 3, window),
+                expected
+            );
+
+            // Deepening one move at a time, as find_move() does, leaves
+            // shallower entries in the table, which order the moves but
+            // must not change the score.
+            let mut table = TranspositionTable::default();
+            for depth in 1..=3 {
+                negamax(&search, &mut table, &board, cell, depth, window);
+            }
+            assert_eq!(
+                negamax(&search, &mut table, &board, cell, 3, window),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn negamax_fails_soft_outside_the_window() {
+        let search = Search::new(2);
+
+        for (board, cell) in random_positions(40) {
+            let exact = minimax(&board, cell, 2);
+
+            for (alpha, beta) in [
+                (exact - 50, exact + 50),
+                (exact + 1, exact + 100),
+                (exact - 100, exact - 1),
+                (exact - 1, exact),
+                (exact, exact + 1),
+            ] {
+                let score = negamax(
+                    &search,
+                    &mut TranspositionTable::default(),
+                    &board,
+                    cell,
+                    2,
+                    (alpha, beta),
+                );
+
+                // A score at or below alpha is an upper bound on the
+                // true score, and one at or above beta a lower bound.
+                if score <= alpha {
+                    assert!(exact <= score, "{} in ({}, {})", score, alpha, beta);
+                } else if score >= beta {
+                    assert!(exact >= score, "{} in ({}, {})", score, alpha, beta);
+                } else {
+                    assert_eq!(score, exact, "in ({}, {})", alpha, beta);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solver_entries_are_on_the_searchs_scale() {
+        let search = Search::new(1);
+
+        for (board, cell) in random_positions(9) {
+            let solution = board.solve_endgame(cell);
+            let expected = score_for_difference(solution.score);
+
+            // Searching to the end gives the solver's score, whether or
+            // not the table already holds what the solver learned.
+            let depth = 2 * board.count_cells(Cell::Empty);
+            let window = (-INFINITY, INFINITY);
+            let mut fresh = TranspositionTable::default();
+            assert_eq!(
+                negamax(&search, &mut fresh, &board, cell, depth, window),
+                expected
+            );
+
+            let mut solved = TranspositionTable::default();
+            board.solve_endgame_with(cell, &mut solved);
+            assert_eq!(
+                negamax(&search, &mut solved, &board, cell, depth, window),
+                expected
+            );
+            assert_eq!(difference_for_score(expected), solution.score);
+        }
+    }
+
+    #[test]
+    fn timed_searches_return_a_valid_move() {
+        let mut search = Search::for_level(Level::Expert);
+        search.set_think_time(Some(Duration::from_millis(50)));
+
+        for (board, cell) in random_positions(30) {
+            let start = Instant::now();
+            let mv = search.find_move(&board, cell);
+            assert!(start.elapsed() < Duration::from_secs(5));
+
+            let loc = mv.get_loc().expect("a move was found");
+            assert!(Movement::new(&board, loc, cell).is_valid());
+        }
+    }
+
+    #[test]
+    fn canceled_searches_stop() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut search = Search::new(30);
+        search.set_cancel_flag(cancel.clone());
+
+        let canceler = {
+            let cancel = cancel.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                cancel.store(true, atomic::Ordering::Relaxed);
+            })
+        };
+
+        // Thirty moves deep would take far longer than this.
+        let start = Instant::now();
+        search.find_move(&Board::new(8, 8), Cell::Black);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        canceler.join().unwrap();
+
+        // Already canceled, it gives up after the first pass.
+        let start = Instant::now();
+        search.find_move(&Board::new(10, 10), Cell::Black);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
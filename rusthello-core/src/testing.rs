@@ -0,0 +1,74 @@
+//! Helpers shared by the tests. The integration tests include this
+//! file too, so it uses only what the crate makes public.
+
+use super::*;
+
+/// A game in which each player makes a valid move chosen at random, or
+/// passes if they have none. The same seed always gives the same game.
+pub struct RandomGame {
+    random: Random,
+    board: Board,
+    cell: Cell,
+}
+
+impl RandomGame {
+    /// Starts a game on a new board of the size given, with black to move.
+    pub fn new(width: usize, height: usize, seed: u64) -> RandomGame {
+        RandomGame {
+            random: Random::new(seed),
+            board: Board::new(width, height),
+            cell: Cell::Black,
+        }
+    }
+
+    /// The board as the moves so far have left it.
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The cell of the player to move next.
+    pub fn get_cell(&self) -> Cell {
+        self.cell
+    }
+
+    /// True once neither player can move.
+    pub fn is_over(&self) -> bool {
+        self.board.is_game_over()
+    }
+
+    /// Plays one turn for the player to move, and returns the move,
+    /// or Pass if they had none.
+    pub fn play_turn(&mut self) -> Play {
+        self.play_turn_watching(|_| {})
+    }
+
+    /// Plays one turn like play_turn(), but one flip at a time, as the
+    /// user interface animates it, passing the board to 'watch' after
+    /// the disc is placed and after each flip.
+    pub fn play_turn_watching(&mut self, mut watch: impl FnMut(&Board)) -> Play {
+        let moves = self.board.find_valid_moves(self.cell);
+        let play = if moves.is_empty() {
+            Play::Pass
+        } else {
+            let mut mv = moves[self.random.below(moves.len())].clone();
+            let loc = mv.get_loc().expect("valid moves have a location");
+            while mv.play_one(&mut self.board) {
+                watch(&self.board);
+            }
+            Play::Move(loc)
+        };
+
+        self.cell = self.cell.flipped();
+        play
+    }
+
+    /// Plays turns until the board has no more than 'empties' empty
+    /// cells, or the game ends, and returns the transcript of them.
+    pub fn play_until(&mut self, empties: usize) -> Transcript {
+        let mut transcript = Transcript::new();
+        while self.board.count_cells(Cell::Empty) > empties && !self.is_over() {
+            transcript.push(self.play_turn());
+        }
+        transcript
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use super::*;
+
+/// The computer choosing a move on another thread, so that the user
+/// interface need not wait for it. Dropping this cancels the search.
+pub struct Thinking {
+    id: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+/// The move the computer chose, and which search chose it.
+pub struct Thought {
+    /// The id of the search, so stale results can be ignored.
+    pub id: u64,
+    /// The move that was chosen.
+    pub movement: Movement,
+}
+
+impl Thinking {
+    /// Starts 'player' choosing a move for 'cell' on a copy of the
+    /// board. When it has chosen, it passes the move to 'deliver', on
+    /// the search's thread; if the search is canceled first, 'deliver'
+    /// is not called. The 'id' is returned with the move, to tell which
+    /// search it came from.
+    pub fn start(
+        id: u64,
+        player: &Player,
+        board: &Board,
+        cell: Cell,
+        deliver: impl FnOnce(Thought) + Send + 'static,
+    ) -> Thinking {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut player = player.clone();
+        let board = board.clone();
+
+        player.set_cancel_flag(cancel.clone());
+
+        let canceled = cancel.clone();
+        thread::spawn(move || {
+            let movement = player.choose_move(&board, cell);
+            if !canceled.load(Ordering::Relaxed) {
+                deliver(Thought { id, movement });
+            }
+        });
+
+        Thinking { id, cancel }
+    }
+
+    /// The id given when the search started.
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for Thinking {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
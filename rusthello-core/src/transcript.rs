@@ -0,0 +1,252 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::*;
+
+/// How a pass is written in a transcript.
+const PASS: &str = "--";
+
+/// A record of the moves of a game, in order. As text, this uses the
+/// usual notation where each move is written as its location and the
+/// moves are simply run together, as in "f5d6c3". Passes are written as
+/// "--"; they may also be left out, as they are implied whenever a player
+/// has no valid move.
+///
+/// Use Game::from_transcript() to replay a transcript, and
+/// Game::to_transcript() to get one.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Transcript {
+    moves: Vec<Play>,
+}
+
+/// One turn in a game; either a move at a location, or a pass
+/// by a player who has no valid move.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Play {
+    /// A disc placed at a location.
+    Move(Loc),
+    /// A turn given up.
+    Pass,
+}
+
+impl Transcript {
+    /// Creates an empty transcript.
+    pub fn new() -> Transcript {
+        Transcript::default()
+    }
+
+    /// The moves and passes, in the order they were played.
+    pub fn get_moves(&self) -> &[Play] {
+        &self.moves
+    }
+
+    /// Adds a move or pass to the end of the transcript.
+    pub fn push(&mut self, play: Play) {
+        self.moves.push(play);
+    }
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for play in &self.moves {
+            write!(f, "{}", play)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Play {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Play::Move(loc) => write!(f, "{}", loc),
+            Play::Pass => f.write_str(PASS),
+        }
+    }
+}
+
+impl FromStr for Transcript {
+    type Err = TranscriptError;
+
+    /// Parses a transcript like "f5d6c3"; whitespace between
+    /// moves is permitted, but not required.
+    fn from_str(text: &str) -> Result<Transcript, TranscriptError> {
+        let mut transcript = Transcript::new();
+        let mut rest = text.trim_start();
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix(PASS) {
+                transcript.push(Play::Pass);
+                rest = after.trim_start();
+                continue;
+            }
+
+            let letters = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            let end = rest[letters..]
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(rest.len(), |digits| letters + digits);
+
+            // A move needs at least one character, even if it's
+            // not valid, or we would never get past it.
+            let end = end.max(rest.chars().next().map_or(1, char::len_utf8));
+            let loc = rest[..end].parse().map_err(TranscriptError::Parse)?;
+            transcript.push(Play::Move(loc));
+            rest = rest[end..].trim_start();
+        }
+
+        Ok(transcript)
+    }
+}
+
+/// The errors that can occur when reading or replaying a transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TranscriptError {
+    /// Part of the transcript was not a location.
+    Parse(ParseLocError),
+    /// A move or pass was not valid when it was played.
+    InvalidMove {
+        /// Which move it was, counting from 1.
+        number: usize,
+        /// The move or pass itself.
+        play: Play,
+    },
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::Parse(err) => write!(f, "{}", err),
+            TranscriptError::InvalidMove { number, play } => {
+                write!(f, "move {} ({}) is not valid", number, play)
+            }
+        }
+    }
+}
+
+impl Error for TranscriptError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    /// Plays a random game on a board of the size given, and returns
+    /// its transcript, with the passes written in, and the final board.
+    fn random_game(width: usize, height: usize, seed: u64) -> (Transcript, Board) {
+        let mut game = RandomGame::new(width, height, seed);
+        let transcript = game.play_until(0);
+        (transcript, game.get_board().clone())
+    }
+
+    #[test]
+    fn transcripts_read_back_as_written() {
+        let text = "f5d6--c3aa12j10--z1";
+        let transcript: Transcript = text.parse().unwrap();
+
+        assert_eq!(
+            transcript.get_moves(),
+            &[
+                Play::Move(Loc::new(5, 4)),
+                Play::Move(Loc::new(3, 5)),
+                Play::Pass,
+                Play::Move(Loc::new(2, 2)),
+                Play::Move(Loc::new(26, 11)),
+                Play::Move(Loc::new(9, 9)),
+                Play::Pass,
+                Play::Move(Loc::new(25, 0)),
+            ]
+        );
+        assert_eq!(transcript.to_string(), text);
+    }
+
+    #[test]
+    fn spacing_and_case_are_ignored() {
+        let spaced: Transcript = " F5 d6\t-- C3\n".parse().unwrap();
+        let plain: Transcript = "f5d6--c3".parse().unwrap();
+        assert_eq!(spaced, plain);
+        assert_eq!(spaced.to_string(), "f5d6--c3");
+    }
+
+    #[test]
+    fn bad_locations_are_rejected() {
+        for text in ["f5x", "f5d0", "f5!", "f5-d6"] {
+            let err = text.parse::<Transcript>().err();
+            assert!(
+                matches!(err, Some(TranscriptError::Parse(_))),
+                "{:?} parsed",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn random_games_replay_from_their_text() {
+        // 8x8 boards use the bitboard; the wider ones have columns
+        // past 'i', and the smaller ones often end in passes.
+        let mut passes = 0;
+
+        for (width, height) in [(8, 8), (12, 10), (4, 4), (6, 4)] {
+            for seed in 0..10 {
+                let (transcript, board) = random_game(width, height, seed);
+                let text = transcript.to_string();
+                let parsed: Transcript = text.parse().unwrap();
+                assert_eq!(parsed, transcript, "{}", text);
+
+                let game = Game::from_transcript(Board::new(width, height), &parsed).unwrap();
+                assert_eq!(game.to_board().get_hash(), board.get_hash(), "{}", text);
+                assert_eq!(game.to_transcript(), transcript, "{}", text);
+
+                passes += transcript
+                    .get_moves()
+                    .iter()
+                    .filter(|&&play| play == Play::Pass)
+                    .count();
+            }
+        }
+        assert!(passes > 0, "no game had a pass");
+    }
+
+    #[test]
+    fn passes_may_be_left_out() {
+        // The first small game that has a pass in it.
+        let (transcript, board) = (0..)
+            .map(|seed| random_game(4, 4, seed))
+            .find(|(transcript, _)| transcript.get_moves().contains(&Play::Pass))
+            .unwrap();
+
+        let mut moves_only = Transcript::new();
+        for &play in transcript.get_moves() {
+            if play != Play::Pass {
+                moves_only.push(play);
+            }
+        }
+
+        let game = Game::from_transcript(Board::new(4, 4), &moves_only).unwrap();
+        assert_eq!(game.to_board().get_hash(), board.get_hash());
+    }
+
+    #[test]
+    fn invalid_moves_are_numbered() {
+        let transcript: Transcript = "f5d6f5".parse().unwrap();
+        let err = Game::from_transcript(Board::new(8, 8), &transcript).err();
+        assert_eq!(
+            err,
+            Some(TranscriptError::InvalidMove {
+                number: 3,
+                play: Play::Move(Loc::new(5, 4)),
+            })
+        );
+
+        let transcript: Transcript = "--".parse().unwrap();
+        let err = Game::from_transcript(Board::new(8, 8), &transcript).err();
+        assert_eq!(
+            err,
+            Some(TranscriptError::InvalidMove {
+                number: 1,
+                play: Play::Pass,
+            })
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use super::*;
+
+/// The most cells a board of the usual sizes can have; the Zobrist
+/// keys for these are worked out in advance. Larger boards work out
+/// the keys for the cells past these as they need them.
+const MAX_CELLS: usize = Board::MAX_SIZE * Board::MAX_SIZE;
+
+/// The random keys that make up a Zobrist hash: a position's hash is the
+/// exclusive-or of the keys for each disc on the board, so it can be kept
+/// up to date as discs change rather than worked out anew each time.
+const ZOBRIST_KEYS: [[u64; 2]; MAX_CELLS] = make_zobrist_keys();
+
+/// Mixed into the hash when White is to move, so that the same
+/// discs with different players to move are different positions.
+const WHITE_TO_MOVE: u64 = 0x5851_f42d_4c95_7f2d;
+
+/// Generates the Zobrist keys with splitmix64, seeded by the cell
+/// index and color, so they are the same on every run.
+const fn make_zobrist_keys() -> [[u64; 2]; MAX_CELLS] {
+    let mut keys = [[0; 2]; MAX_CELLS];
+    let mut index = 0;
+
+    while index < MAX_CELLS {
+        keys[index][0] = splitmix(index as u64 * 2);
+        keys[index][1] = splitmix(index as u64 * 2 + 1);
+        index += 1;
+    }
+
+    keys
+}
+
+const fn splitmix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Returns the Zobrist key for 'cell' in the cell with the index given
+/// (that is, 'y * width + x'); an empty cell contributes nothing.
+pub fn zobrist_key(index: usize, cell: Cell) -> u64 {
+    let color = match cell {
+        Cell::Empty => return 0,
+        Cell::Black => 0,
+        Cell::White => 1,
+    };
+
+    match ZOBRIST_KEYS.get(index) {
+        Some(keys) => keys[color],
+        None => splitmix(index as u64 * 2 + color as u64),
+    }
+}
+
+/// Says how a stored score relates to the true score of a position,
+/// which depends on the alpha-beta window it was searched with.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The search cut off early; the true score is at least this.
+    Lower,
+    /// No move reached alpha; the true score is at most this.
+    Upper,
+}
+
+impl Bound {
+    /// Classifies a score found by searching with the window given.
+    pub fn for_score(score: i32, alpha: i32, beta: i32) -> Bound {
+        if score <= alpha {
+            Bound::Upper
+        } else if score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        }
+    }
+}
+
+/// What a search learned about one position.
+#[derive(Copy, Clone, Debug)]
+pub struct Entry {
+    /// How many moves deep the position was searched.
+    pub depth: u32,
+    /// How 'score' relates to the true score.
+    pub bound: Bound,
+    /// The score, from the point of view of the player to move.
+    pub score: i32,
+    /// The best move found, if there was one; this is worth
+    /// trying first when the position is searched again.
+    pub best_move: Option<Loc>,
+}
+
+impl Entry {
+    /// The depth of an entry for a position that was solved exactly, to
+    /// the end of the game; these settle a search of any depth.
+    pub const SOLVED: u32 = u32::MAX;
+
+    /// Returns the stored score if it settles the position for a search
+    /// 'depth' moves deep with the window given; if not, this returns
+    /// None and the position must be searched.
+    pub fn get_cutoff(&self, depth: u32, alpha: i32, beta: i32) -> Option<i32> {
+        if self.depth < depth {
+            return None;
+        }
+
+        let settled = match self.bound {
+            Bound::Exact => true,
+            Bound::Lower => self.score >= beta,
+            Bound::Upper => self.score <= alpha,
+        };
+
+        Some(self.score).filter(|_| settled)
+    }
+}
+
+/// Remembers the results of searching positions, so that a position
+/// reached again by a different order of moves need not be searched again.
+/// The table has a fixed size; when two positions need the same slot,
+/// the newer one replaces the older, but a position searched again keeps
+/// its old entry if that was searched deeper.
+///
+/// The search and the endgame solver can share a table: the solver stores
+/// its entries as SOLVED, with scores on the search's scale, and uses only
+/// the moves from the search's entries. A table may be kept from one move
+/// to the next, as the positions it holds may come up again.
+pub struct TranspositionTable {
+    slots: Vec<Option<(u64, Entry)>>,
+}
+
+impl TranspositionTable {
+    /// The number of slots in a table, unless we ask for another size.
+    pub const DEFAULT_SIZE: usize = 1 << 16;
+
+    /// Creates an empty table with room for at least 'size'
+    /// positions; this is rounded up to a power of two.
+    pub fn new(size: usize) -> TranspositionTable {
+        TranspositionTable {
+            slots: vec![None; size.max(1).next_power_of_two()],
+        }
+    }
+
+    /// Looks up what is known about the position with the hash given,
+    /// with 'cell' to move.
+    pub fn probe(&self, hash: u64, cell: Cell) -> Option<&Entry> {
+        let key = position_key(hash, cell);
+        match &self.slots[self.index(key)] {
+            Some((k, entry)) if *k == key => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// Records what a search learned about the position with the
+    /// hash given, with 'cell' to move.
+    pub fn store(&mut self, hash: u64, cell: Cell, entry: Entry) {
+        let key = position_key(hash, cell);
+        let index = self.index(key);
+
+        let keep_old = match &self.slots[index] {
+            Some((k, old)) => *k == key && old.depth > entry.depth,
+            None => false,
+        };
+
+        if !keep_old {
+            self.slots[index] = Some((key, entry));
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key as usize) & (self.slots.len() - 1)
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> TranspositionTable {
+        TranspositionTable::new(TranspositionTable::DEFAULT_SIZE)
+    }
+}
+
+/// Combines a board's hash with the player to move, to give
+/// a key for the position.
+pub fn position_key(hash: u64, cell: Cell) -> u64 {
+    if cell == Cell::White {
+        hash ^ WHITE_TO_MOVE
+    } else {
+        hash
+    }
+}
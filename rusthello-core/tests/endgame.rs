@@ -0,0 +1,21 @@
+use rusthello_core::{Board, Cell, Game, Outcome, Solution, Transcript};
+
+fn solve_after(moves: &str, cell: Cell) -> Solution {
+    let transcript: Transcript = moves.parse().unwrap();
+    let game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+    game.to_board().solve_endgame(cell)
+}
+
+#[test]
+fn solutions_report_the_outcome_and_score() {
+    // Black takes every white disc in nine moves, leaving 13 discs.
+    let solution = solve_after("d3c3b3d2e1d6d7e3f4", Cell::Black);
+    assert_eq!(solution.get_outcome(), Outcome::Win);
+    assert_eq!(solution.score, 13);
+    assert_eq!(solution.to_string(), "win by 13");
+
+    let solution = solve_after("d3c3b3d2e1d6d7e3f4", Cell::White);
+    assert_eq!(solution.get_outcome(), Outcome::Loss);
+    assert_eq!(solution.score, -13);
+    assert!(solution.best_move.get_loc().is_none());
+}
@@ -0,0 +1,198 @@
+use rusthello_core::*;
+
+// Not every test uses every helper.
+#[allow(dead_code)]
+#[path = "../src/testing.rs"]
+mod testing;
+
+use testing::*;
+
+/// The board sizes to play on; 8x8 boards use the bitboard,
+/// and the others use the general code.
+const SIZES: [(usize, usize); 5] = [(8, 8), (4, 4), (6, 6), (10, 8), (7, 5)];
+
+/// How many random games to play on each size of board.
+const GAMES_PER_SIZE: u64 = 20;
+
+/// The directions a line of flips can run in.
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A plain copy of the board's cells, used to work out the rules
+/// again without the board's own code, so the two can be compared.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    fn of(board: &Board) -> Grid {
+        Grid {
+            width: board.get_width(),
+            height: board.get_height(),
+            cells: board.locations().map(|loc| board[loc]).collect(),
+        }
+    }
+
+    fn get(&self, x: isize, y: isize) -> Option<Cell> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            None
+        } else {
+            Some(self.cells[y as usize * self.width + x as usize])
+        }
+    }
+
+    fn count(&self, cell: Cell) -> usize {
+        self.cells.iter().filter(|&&c| c == cell).count()
+    }
+
+    /// The locations 'cell' would flip by moving at 'loc'.
+    fn flips(&self, loc: Loc, cell: Cell) -> Vec<Loc> {
+        let mut flips = Vec::new();
+        if self.get(loc.x as isize, loc.y as isize) != Some(Cell::Empty) {
+            return flips;
+        }
+
+        for &(dx, dy) in &DIRECTIONS {
+            let mut line = Vec::new();
+            let (mut x, mut y) = (loc.x as isize + dx, loc.y as isize + dy);
+
+            while self.get(x, y) == Some(cell.flipped()) {
+                line.push(Loc::new(x as usize, y as usize));
+                x += dx;
+                y += dy;
+            }
+
+            if self.get(x, y) == Some(cell) {
+                flips.extend(line);
+            }
+        }
+        flips
+    }
+
+    fn valid_moves(&self, cell: Cell) -> Vec<Loc> {
+        let locs = (0..self.height).flat_map(|y| (0..self.width).map(move |x| Loc::new(x, y)));
+        locs.filter(|&loc| !self.flips(loc, cell).is_empty())
+            .collect()
+    }
+
+    fn play(&self, loc: Loc, cell: Cell) -> Grid {
+        let mut next = self.clone();
+        for l in self.flips(loc, cell).into_iter().chain(Some(loc)) {
+            next.cells[l.y * self.width + l.x] = cell;
+        }
+        next
+    }
+}
+
+/// Checks everything we know should be true of 'board',
+/// with 'cell' to move.
+fn check_board(board: &Board, cell: Cell) {
+    let grid = Grid::of(board);
+    let total = board.get_width() * board.get_height();
+
+    let counts: Vec<usize> = [Cell::Empty, Cell::Black, Cell::White]
+        .iter()
+        .map(|&c| board.count_cells(c))
+        .collect();
+    assert_eq!(counts.iter().sum::<usize>(), total);
+    assert_eq!(counts[0], grid.count(Cell::Empty));
+    assert_eq!(counts[1], grid.count(Cell::Black));
+    assert_eq!(counts[2], grid.count(Cell::White));
+
+    if let Some(bits) = board.get_bitboard() {
+        for loc in board.locations() {
+            let bit = 1u64 << (loc.y * 8 + loc.x);
+            assert_ne!(
+                bits.get_mask(board[loc]) & bit,
+                0,
+                "bitboard differs at {}",
+                loc
+            );
+        }
+    }
+
+    for c in [Cell::Black, Cell::White] {
+        let mut found: Vec<Loc> = board
+            .find_valid_moves(c)
+            .iter()
+            .map(|mv| mv.get_loc().expect("valid moves have a location"))
+            .collect();
+        found.sort_by_key(|loc| (loc.y, loc.x));
+
+        assert_eq!(found, grid.valid_moves(c), "{} moves", c.name());
+        assert_eq!(board.count_valid_moves(c), found.len());
+        assert_eq!(board.has_valid_move(c), !found.is_empty());
+    }
+
+    let any_move = [Cell::Black, Cell::White]
+        .iter()
+        .any(|&c| !grid.valid_moves(c).is_empty());
+    assert_eq!(board.is_game_over(), !any_move);
+
+    for mv in board.find_valid_moves(cell) {
+        check_movement(board, cell, mv);
+    }
+}
+
+/// Plays 'mv' one flip at a time, checking that it places a disc on an
+/// empty cell and then flips only the opponent's discs, and that it
+/// ends with the board the rules say it should.
+fn check_movement(board: &Board, cell: Cell, mut mv: Movement) {
+    let loc = mv.get_loc().unwrap();
+    let expected = Grid::of(board).play(loc, cell);
+    let flip_count = mv.get_flip_count();
+    let mut board = board.clone();
+    let mut steps = 0;
+
+    loop {
+        let before = Grid::of(&board);
+        if !mv.play_one(&mut board) {
+            break;
+        }
+        let after = Grid::of(&board);
+
+        let changed: Vec<usize> = (0..before.cells.len())
+            .filter(|&i| before.cells[i] != after.cells[i])
+            .collect();
+        assert_eq!(changed.len(), 1, "each step changes one cell");
+
+        let old = before.cells[changed[0]];
+        let wanted = if steps == 0 {
+            Cell::Empty
+        } else {
+            cell.flipped()
+        };
+        assert_eq!(old, wanted, "step {} of the move at {}", steps, loc);
+        assert_eq!(after.cells[changed[0]], cell);
+        steps += 1;
+    }
+
+    assert_eq!(steps, flip_count + 1);
+    assert_eq!(Grid::of(&board), expected, "after the move at {}", loc);
+}
+
+#[test]
+fn random_games_keep_the_board_consistent() {
+    for (width, height) in SIZES {
+        for seed in 0..GAMES_PER_SIZE {
+            // Checks the board before every move, and at the end.
+            let mut game = RandomGame::new(width, height, seed);
+            while !game.is_over() {
+                check_board(game.get_board(), game.get_cell());
+                game.play_turn();
+            }
+            check_board(game.get_board(), game.get_cell());
+        }
+    }
+}
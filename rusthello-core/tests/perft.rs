@@ -0,0 +1,42 @@
+use rusthello_core::*;
+
+/// The well-known counts for the standard 8x8 board, from one move
+/// deep onwards; there are no passes or finished games this early.
+const STANDARD_COUNTS: [u64; 9] = [4, 12, 56, 244, 1396, 8200, 55092, 390216, 3005288];
+
+#[test]
+fn standard_board_matches_known_counts() {
+    let board = Board::new(8, 8);
+
+    for (depth, &expected) in (1..).zip(STANDARD_COUNTS.iter()) {
+        assert_eq!(board.perft(Cell::Black, depth), expected, "depth {}", depth);
+    }
+}
+
+#[test]
+fn counts_are_the_same_for_either_player_to_move() {
+    // The start position is symmetric, so white moving
+    // first must find as many positions as black.
+    let board = Board::new(8, 8);
+
+    for depth in 1..=5 {
+        assert_eq!(
+            board.perft(Cell::White, depth),
+            board.perft(Cell::Black, depth)
+        );
+    }
+}
+
+#[test]
+fn positions_reached_by_a_game_count_the_same() {
+    // The four first moves are alike by symmetry, so each
+    // leads to a quarter of the positions.
+    let transcript: Transcript = "f5".parse().unwrap();
+    let game = Game::from_transcript(Board::new(8, 8), &transcript).unwrap();
+
+    assert_eq!(game.to_board().perft(Cell::White, 1), 3);
+    assert_eq!(
+        game.to_board().perft(Cell::White, 8),
+        STANDARD_COUNTS[8] / 4
+    );
+}
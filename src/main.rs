@@ -3,21 +3,54 @@ use cursive::view::*;
 use cursive::views::*;
 use cursive::*;
 use cursive_aligned_view::Alignable;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
 
-mod game;
-mod iterext;
+mod options;
 mod ui;
 
-use crate::game::*;
+use crate::options::*;
 use crate::ui::*;
+use rusthello_core::*;
 
 fn main() {
+    let options = Options::from_args();
+
+    if let Some(games) = &options.build_book {
+        build_book(games, &options.book_path());
+        return;
+    }
+
+    let mut game = match &options.load {
+        Some(path) => load_game(path),
+        None => Game::from_transcript(Board::new(options.width, options.height), &options.moves)
+            .unwrap_or_else(|err| exit_with_error(err)),
+    };
+    game.set_flip_delay(options.flip_delay);
+
+    let book = load_book(&options);
+    for (cell, player) in [(Cell::Black, &options.black), (Cell::White, &options.white)] {
+        let mut player = player.clone();
+        if let (Player::Computer(search), Some(book)) = (&mut player, &book) {
+            search.set_book(book.clone());
+        }
+        game.set_player(cell, player);
+    }
+
+    if options.solve {
+        print_solution(&game);
+        return;
+    }
+    let game = game.into_handle();
+
     let mut siv = Cursive::default();
     siv.set_fps(60);
-
-    let board = Board::new(8, 8);
-    let game = Game::new(board).into_ref();
-    let boardview = BoardView::new(game.clone());
+    let mut boardview = BoardView::new(game.clone());
+    boardview.set_save_path(options.save_path());
 
     let scoreboard = ShadowView::new(Layer::with_color(
         Panel::new(ScoreboardView::new(game).fixed_size((18, 3))),
@@ -28,10 +61,90 @@ fn main() {
     siv.add_fullscreen_layer(Layer::with_color(
         LinearLayout::vertical().child(
             LinearLayout::horizontal()
-                .child(boardview)
+                .child(boardview.scrollable().scroll_x(true))
                 .child(scoreboard),
         ),
         ColorStyle::back(Color::Dark(BaseColor::Blue)),
     ));
     siv.run();
 }
+
+/// Prints the outcome of the game, for the player whose move it is.
+fn print_solution(game: &Game) {
+    let cell = game.get_next_move();
+
+    if game.is_over() {
+        println!("The game is over.");
+    } else {
+        let solution = game.to_board().solve_endgame(cell);
+        println!("{} to move: {}", cell.name(), solution);
+    }
+}
+
+/// Reads a saved game from a file; if this fails, it exits the process.
+fn load_game(path: &Path) -> Game {
+    let file = File::open(path).unwrap_or_else(|err| exit_with_error(err));
+    Game::load(&mut BufReader::new(file)).unwrap_or_else(|err| exit_with_error(err))
+}
+
+/// Reads the opening book, if there is one. A book given with --book
+/// must be there, but the default book is used only if it exists. If
+/// the book can't be read, this exits the process.
+fn load_book(options: &Options) -> Option<Arc<OpeningBook>> {
+    let path = options.book_path();
+    if options.book.is_none() && !path.exists() {
+        return None;
+    }
+
+    let file = File::open(&path).unwrap_or_else(|err| exit_with_error(err));
+    let book = OpeningBook::load(&mut BufReader::new(file))
+        .unwrap_or_else(|err| exit_with_error(format!("{}: {}", path.display(), err)));
+    Some(Arc::new(book))
+}
+
+/// Builds an opening book from a file of transcripts, one per line, and
+/// writes it to 'book_path'; blank lines and lines starting with '#' are
+/// skipped. If this fails, it exits the process.
+fn build_book(games_path: &Path, book_path: &Path) {
+    let games = File::open(games_path).unwrap_or_else(|err| exit_with_error(err));
+    let mut book = OpeningBook::new();
+    let mut game_count = 0;
+
+    for (index, line) in BufReader::new(games).lines().enumerate() {
+        let line = line.unwrap_or_else(|err| exit_with_error(err));
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let added = line
+            .parse()
+            .and_then(|transcript| book.add_game(&transcript));
+        if let Err(err) = added {
+            exit_with_error(format!("{}:{}: {}", games_path.display(), index + 1, err));
+        }
+        game_count += 1;
+    }
+
+    if book.is_empty() {
+        exit_with_error(format!("{} contains no games", games_path.display()));
+    }
+
+    let file = File::create(book_path).unwrap_or_else(|err| exit_with_error(err));
+    let mut out = BufWriter::new(file);
+    book.save(&mut out)
+        .and_then(|()| out.flush())
+        .unwrap_or_else(|err| exit_with_error(err));
+
+    println!(
+        "Read {} games; wrote {} positions to {}",
+        game_count,
+        book.len(),
+        book_path.display()
+    );
+}
+
+fn exit_with_error(err: impl Display) -> ! {
+    eprintln!("{}", err);
+    process::exit(1);
+}
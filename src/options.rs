@@ -0,0 +1,298 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use rusthello_core::*;
+
+/// The file games are saved to, unless a game was loaded from another.
+const DEFAULT_SAVE_FILE: &str = "rusthello.sav";
+
+/// The opening book the computer uses, if there is no --book option.
+const DEFAULT_BOOK_FILE: &str = "rusthello.book";
+
+const USAGE: &str = "\
+usage: rusthello [OPTIONS]
+
+    --size WIDTHxHEIGHT   board size, or a single number for a square board
+    --black PLAYER        who plays black; the default is 'human'
+    --white PLAYER        who plays white; the default is 'computer'
+    --color COLOR         play 'black', 'white' or a 'random' color
+                          against the computer
+    --level LEVEL         how well the computer plays: 'beginner', 'easy',
+                          'medium', 'hard' or 'expert'; the default is 'hard'
+    --endgame EMPTIES     how many empty cells are left when the computer
+                          starts solving the game exactly; the default is 12
+    --think-time SECONDS  let the computer think this long over each move,
+                          looking as far ahead as it can in that time
+    --flip-delay MS       how many milliseconds each disc takes to flip;
+                          the default is 100
+    --instant             play each move out at once, without flipping
+                          the discs one at a time
+    --moves TRANSCRIPT    moves to play before starting, like 'f5d6c3'
+    --load FILE           resume a saved game
+    --solve               print the exact outcome of the game with perfect
+                          play, then exit; this is slow if many cells are empty
+    --book FILE           the opening book for the computer to use; the
+                          default is 'rusthello.book', if it exists
+    --build-book GAMES    build the opening book from a file of transcripts,
+                          one game per line, then exit
+
+PLAYER is 'human', 'computer', a LEVEL for the computer, or
+'computer:DEPTH' to set how many moves ahead the computer looks.
+It may also be 'mcts' for the computer to use Monte Carlo tree
+search instead, which suits unusual board sizes; this thinks for a
+second over each move, or 'mcts:GAMES' plays out that many games.
+--think-time overrides the depth or number of games.
+";
+
+/// How well the computer plays, unless told otherwise.
+const DEFAULT_LEVEL: Level = Level::Hard;
+
+/// The settings taken from the command line.
+pub struct Options {
+    /// The width of the board, in cells.
+    pub width: usize,
+    /// The height of the board, in cells.
+    pub height: usize,
+    /// Who plays black.
+    pub black: Player,
+    /// Who plays white.
+    pub white: Player,
+    /// How long each disc takes to flip.
+    pub flip_delay: Duration,
+    /// Moves to play before the game begins.
+    pub moves: Transcript,
+    /// A saved game to resume.
+    pub load: Option<PathBuf>,
+    /// If true, we solve the game instead of playing it.
+    pub solve: bool,
+    /// The opening book to use, if not the default one.
+    pub book: Option<PathBuf>,
+    /// A file of transcripts to build the opening book from,
+    /// instead of playing.
+    pub build_book: Option<PathBuf>,
+}
+
+impl Options {
+    /// Reads the options from the command line; if they are not
+    /// valid, this prints usage and exits the process.
+    pub fn from_args() -> Options {
+        match Options::parse(env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{}", message);
+                eprint!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut sized = false;
+        let mut chose_players = false;
+        let mut color = None;
+        let mut endgame_empties = None;
+        let mut think_time = None;
+        let mut level = None;
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{} requires a value", arg))
+            };
+
+            match arg.as_str() {
+                "--size" => {
+                    let (width, height) = parse_size(&value()?)?;
+                    options.width = width;
+                    options.height = height;
+                    sized = true;
+                }
+                "--black" => {
+                    options.black = parse_player(&value()?)?;
+                    chose_players = true;
+                }
+                "--white" => {
+                    options.white = parse_player(&value()?)?;
+                    chose_players = true;
+                }
+                "--color" => color = Some(parse_color(&value()?)?),
+                "--level" => {
+                    let text = value()?;
+                    let found = Level::from_name(&text)
+                        .ok_or_else(|| format!("'{}' is not a level", text))?;
+                    level = Some(found);
+                }
+                "--moves" => {
+                    options.moves = value()?.parse().map_err(|e| format!("{}", e))?;
+                }
+                "--load" => options.load = Some(value()?.into()),
+                "--solve" => options.solve = true,
+                "--book" => options.book = Some(value()?.into()),
+                "--build-book" => options.build_book = Some(value()?.into()),
+                "--endgame" => {
+                    let text = value()?;
+                    let empties = text
+                        .parse()
+                        .map_err(|_| format!("'{}' is not a number of cells", text))?;
+                    endgame_empties = Some(empties);
+                }
+                "--flip-delay" => options.flip_delay = parse_flip_delay(&value()?)?,
+                "--instant" => options.flip_delay = Duration::ZERO,
+                "--think-time" => think_time = Some(parse_think_time(&value()?)?),
+                _ => return Err(format!("unknown option '{}'", arg)),
+            }
+        }
+
+        if options.load.is_some() && !options.moves.get_moves().is_empty() {
+            return Err("--moves and --load cannot be used together".to_string());
+        }
+
+        if let Some(color) = color {
+            if chose_players {
+                return Err("--color cannot be used with --black or --white".to_string());
+            }
+
+            let computer = Player::Computer(Search::for_level(DEFAULT_LEVEL));
+            if color == Cell::Black {
+                options.black = Player::Human;
+                options.white = computer;
+            } else {
+                options.black = computer;
+                options.white = Player::Human;
+            }
+        }
+
+        for player in [&mut options.black, &mut options.white] {
+            if think_time.is_some() {
+                player.set_think_time(think_time);
+            }
+
+            if let Player::Computer(search) = player {
+                if let Some(level) = level {
+                    search.set_level(level);
+                }
+                if let Some(empties) = endgame_empties {
+                    search.set_endgame_empties(empties);
+                }
+            }
+        }
+
+        if options.load.is_some() && sized {
+            return Err("--size and --load cannot be used together".to_string());
+        }
+
+        Ok(options)
+    }
+
+    /// The file the game is saved to; this is the file it was
+    /// loaded from, if any.
+    pub fn save_path(&self) -> PathBuf {
+        self.load
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SAVE_FILE))
+    }
+
+    /// The opening book file; this is the default one unless
+    /// another was given.
+    pub fn book_path(&self) -> PathBuf {
+        self.book
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_BOOK_FILE))
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            width: 8,
+            height: 8,
+            black: Player::Human,
+            white: Player::Computer(Search::for_level(DEFAULT_LEVEL)),
+            flip_delay: Game::DEFAULT_FLIP_DELAY,
+            moves: Transcript::new(),
+            load: None,
+            solve: false,
+            book: None,
+            build_book: None,
+        }
+    }
+}
+
+/// Parses a board size, which is either a single number
+/// for a square board, or a width and height like '8x10'.
+fn parse_size(text: &str) -> Result<(usize, usize), String> {
+    let error = || format!("'{}' is not a board size", text);
+    let mut parts = text.splitn(2, 'x');
+    let width: usize = parts.next().unwrap().parse().map_err(|_| error())?;
+    let height: usize = match parts.next() {
+        Some(height) => height.parse().map_err(|_| error())?,
+        None => width,
+    };
+
+    let limits = Board::MIN_SIZE..=Board::MAX_SIZE;
+    if limits.contains(&width) && limits.contains(&height) {
+        Ok((width, height))
+    } else {
+        Err(format!(
+            "the board must be between {} and {} cells on each side",
+            Board::MIN_SIZE,
+            Board::MAX_SIZE
+        ))
+    }
+}
+
+/// Parses a player, which is 'human', a level like 'easy', 'computer'
+/// optionally followed by how far ahead it looks, like 'computer:6', or
+/// 'mcts' optionally followed by how many games it plays out.
+fn parse_player(text: &str) -> Result<Player, String> {
+    if let Some(level) = Level::from_name(text) {
+        return Ok(Player::Computer(Search::for_level(level)));
+    }
+
+    let mut parts = text.splitn(2, ':');
+
+    match (parts.next().unwrap(), parts.next()) {
+        ("human", None) => Ok(Player::Human),
+        ("computer", None) => Ok(Player::Computer(Search::for_level(DEFAULT_LEVEL))),
+        ("computer", Some(depth)) => match depth.parse() {
+            Ok(depth) if depth > 0 => Ok(Player::Computer(Search::new(depth))),
+            _ => Err(format!("'{}' is not a search depth", depth)),
+        },
+        ("mcts", None) => Ok(Player::MonteCarlo(MonteCarlo::with_default_time())),
+        ("mcts", Some(games)) => match games.parse() {
+            Ok(games) if games > 0 => Ok(Player::MonteCarlo(MonteCarlo::new(games))),
+            _ => Err(format!("'{}' is not a number of games", games)),
+        },
+        _ => Err(format!("'{}' is not a player", text)),
+    }
+}
+
+/// Parses the color the human wants to play; 'random'
+/// picks one of the two.
+fn parse_color(text: &str) -> Result<Cell, String> {
+    match text {
+        "black" => Ok(Cell::Black),
+        "white" => Ok(Cell::White),
+        "random" if Random::from_time().below(2) == 0 => Ok(Cell::Black),
+        "random" => Ok(Cell::White),
+        _ => Err(format!("'{}' is not a color", text)),
+    }
+}
+
+/// Parses a time to think, in seconds, like '2' or '0.5'.
+fn parse_think_time(text: &str) -> Result<Duration, String> {
+    let seconds = text.parse::<f64>().ok().filter(|&s| s > 0.0);
+    seconds
+        .and_then(|s| Duration::try_from_secs_f64(s).ok())
+        .ok_or_else(|| format!("'{}' is not a number of seconds", text))
+}
+
+/// Parses a delay in whole milliseconds, like '250'.
+fn parse_flip_delay(text: &str) -> Result<Duration, String> {
+    text.parse()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("'{}' is not a number of milliseconds", text))
+}
@@ -1,24 +1,52 @@
-use crate::game::*;
 use cursive::event::*;
 use cursive::theme::*;
+use cursive::views::{Dialog, SelectView};
 use cursive::*;
+use rusthello_core::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
 
 /// A view to display the board's cells; it also
 /// tracks the cursor used by the player to make moves.
 pub struct BoardView {
-    game: GameRef,
+    game: GameHandle,
     cursor: Loc,
+    save_path: PathBuf,
+    hints: Hints,
+}
+
+/// Selects what the board view shows in the cells where
+/// the player whose turn it is could move.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Hints {
+    Off,
+    /// A mark in each cell where a move is valid.
+    Moves,
+    /// The number of discs the move in each cell would flip.
+    Flips,
 }
 
 impl BoardView {
-    pub fn new(game: GameRef) -> BoardView {
-        let cursor = game.borrow().to_board().get_board_center();
-        BoardView { game, cursor }
+    /// Creates the view, which shows the game and
+    /// plays the computer's moves in it.
+    pub fn new(game: GameHandle) -> BoardView {
+        let cursor = game.lock().to_board().get_board_center();
+        let save_path = PathBuf::new();
+        BoardView {
+            game,
+            cursor,
+            save_path,
+            hints: Hints::Off,
+        }
     }
 
-    fn get_bg_char(&self, xy: Vec2) -> &'static str {
-        let game = self.game.borrow();
-        let board = game.to_board();
+    /// Sets the file that the game is written to when the player saves it.
+    pub fn set_save_path(&mut self, path: PathBuf) {
+        self.save_path = path;
+    }
+
+    fn get_bg_char(board: &Board, xy: Vec2) -> &'static str {
         const LEFT: usize = 0b0001;
         const UP: usize = 0b0010;
         const RIGHT: usize = 0b0100;
@@ -48,7 +76,7 @@ impl BoardView {
     }
 
     fn move_cursor(&mut self, dx: isize, dy: isize) -> bool {
-        let game = self.game.borrow();
+        let game = self.game.lock();
         let board = game.to_board();
         if let Some(l) = board.offset_within(self.cursor, dx, dy) {
             self.cursor = l;
@@ -58,8 +86,23 @@ impl BoardView {
         }
     }
 
+    /// Returns the location of the cell displayed at 'xy', relative to
+    /// the view; this is None for points on the grid lines between cells,
+    /// or outside the board.
+    fn loc_at(&self, xy: Vec2) -> Option<Loc> {
+        let game = self.game.lock();
+        let board = game.to_board();
+
+        // Cells are drawn at odd co-ordinates; see render().
+        if xy.x % 2 == 1 && xy.y % 2 == 1 {
+            Some(Loc::new(xy.x / 2, xy.y / 2)).filter(|&loc| board.contains(loc))
+        } else {
+            None
+        }
+    }
+
     fn render(&self, printer: &Printer) {
-        let game = self.game.borrow();
+        let game = self.game.lock();
         let board = game.to_board();
         let height = board.get_height();
         let width = board.get_width();
@@ -68,20 +111,24 @@ impl BoardView {
         for y in 0..=height * 2 {
             for x in 0..=width * 2 {
                 let xy = Vec2::new(x, y);
-                printer.print(xy, self.get_bg_char(xy));
+                printer.print(xy, BoardView::get_bg_char(board, xy));
             }
         }
 
-        if !board.is_game_over() {
+        if !game.is_over() {
             printer.print_box((cursor.x * 2, cursor.y * 2), (3, 3), false);
         }
 
+        let hints = self.find_hints(&game);
+
         for loc in board.locations() {
             let cell = board[loc];
             let xy = XY::new(loc.x * 2 + 1, loc.y * 2 + 1);
+            let hint = hints.iter().find(|(l, _)| *l == loc);
+            let text = hint.map_or(cell.to_str(), |(_, text)| text);
 
-            if loc == cursor && !board.is_game_over() {
-                let candidate_move = Movement::new(board, self.cursor, Cell::Black);
+            if loc == cursor && !game.is_over() {
+                let candidate_move = game.get_player_movement(self.cursor);
                 let hilight = if candidate_move.is_valid() {
                     ColorStyle::back(Color::Light(BaseColor::White))
                 } else {
@@ -89,32 +136,61 @@ impl BoardView {
                 };
 
                 printer.with_color(hilight, |p| {
-                    p.print(xy, cell.to_str());
+                    p.print(xy, text);
+                });
+            } else if hint.is_some() {
+                let style = ColorStyle::front(Color::Dark(BaseColor::Green));
+                printer.with_color(style, |p| {
+                    p.print(xy, text);
                 });
             } else {
-                printer.print(xy, cell.to_str());
+                printer.print(xy, text);
             }
         }
     }
+
+    /// Returns the text to show in each cell where the player to move
+    /// could move, according to the hints selected.
+    fn find_hints(&self, game: &Game) -> Vec<(Loc, &'static str)> {
+        const MARK: &str = "·";
+        const COUNTS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        const MANY: &str = "+";
+
+        let cell = game.get_next_move();
+        if self.hints == Hints::Off || cell == Cell::Empty {
+            return Vec::new();
+        }
+
+        let moves = game.to_board().find_valid_moves(cell);
+        let hints = moves.iter().filter_map(|mv| {
+            let text = match self.hints {
+                Hints::Flips => COUNTS.get(mv.get_flip_count()).copied().unwrap_or(MANY),
+                _ => MARK,
+            };
+            mv.get_loc().map(|loc| (loc, text))
+        });
+        hints.collect()
+    }
 }
 
 impl View for BoardView {
     fn draw(&self, printer: &Printer) {
-        if let Ok(mut game) = self.game.try_borrow_mut() {
-            if game.check_move() == Cell::White {
-                let mv = game.get_ai_movement();
-                game.begin_movement(mv);
-            }
-        }
+        self.game.advance();
         self.render(printer);
     }
 
     fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
-        let game = self.game.borrow();
+        let game = self.game.lock();
         let board = game.to_board();
         Vec2::new(board.get_width() * 2 + 1, board.get_height() * 2 + 1)
     }
 
+    fn important_area(&self, _view_size: Vec2) -> Rect {
+        // This keeps the cursor in view when the board is too
+        // large for the screen, and must scroll.
+        Rect::from_size((self.cursor.x * 2, self.cursor.y * 2), (3, 3))
+    }
+
     fn on_event(&mut self, event: Event) -> EventResult {
         use EventResult::*;
 
@@ -124,22 +200,181 @@ impl View for BoardView {
             Event::Key(Key::Left) => move_cursor(self, -1, 0),
             Event::Key(Key::Right) => move_cursor(self, 1, 0),
             Event::Char(' ') => make_move(self),
+            Event::Char('u') => undo(self),
+            Event::Char('r') => redo(self),
+            Event::Char('t') => show_transcript(self),
+            Event::Char('h') => toggle_hints(self),
+            Event::Char('l') => choose_level(self),
+            Event::Char('s') => save(self),
+            Event::Char('x') => resign(self),
             Event::Char('q') => EventResult::with_cb(|s| s.quit()),
+            Event::Mouse {
+                offset,
+                position,
+                event: MouseEvent::Press(MouseButton::Left),
+            } => click(self, position.checked_sub(offset)),
+            Event::Mouse {
+                offset,
+                position,
+                event: MouseEvent::Hold(MouseButton::Left),
+            } => drag(self, position.checked_sub(offset)),
             _ => Ignored,
         };
 
+        // Clicking a cell moves the cursor there; clicking
+        // the cursor's cell makes a move there.
+        fn click(me: &mut BoardView, xy: Option<Vec2>) -> EventResult {
+            match xy.and_then(|xy| me.loc_at(xy)) {
+                Some(loc) if loc == me.cursor => make_move(me),
+                Some(loc) => {
+                    me.cursor = loc;
+                    Consumed(None)
+                }
+                None => Ignored,
+            }
+        }
+
+        // Hover is not supported: cursive has no event for the mouse
+        // moving with no button held, even on terminals that report it.
+        // So the cursor follows the mouse only when dragging.
+        fn drag(me: &mut BoardView, xy: Option<Vec2>) -> EventResult {
+            match xy.and_then(|xy| me.loc_at(xy)) {
+                Some(loc) => {
+                    me.cursor = loc;
+                    Consumed(None)
+                }
+                None => Ignored,
+            }
+        }
+
         fn move_cursor(me: &mut BoardView, dx: isize, dy: isize) -> EventResult {
-            me.move_cursor(dx, dy);
-            Ignored
+            if me.move_cursor(dx, dy) {
+                Consumed(None)
+            } else {
+                Ignored
+            }
         }
 
+        // Moves that are not valid are ignored; the cursor's
+        // colour already shows whether a move is valid.
         fn make_move(me: &mut BoardView) -> EventResult {
-            let mut game = me.game.borrow_mut();
-            if game.check_move() == Cell::Black {
-                let mv = game.get_player_movement(me.cursor);
-                game.begin_immediate_movement(mv);
-            }
+            let _ = if me.game.lock().must_pass() {
+                me.game.pass()
+            } else {
+                me.game.play(me.cursor)
+            };
+            Consumed(None)
+        }
+
+        fn undo(me: &mut BoardView) -> EventResult {
+            let _ = me.game.undo();
             Ignored
         }
+
+        fn redo(me: &mut BoardView) -> EventResult {
+            let _ = me.game.redo();
+            Ignored
+        }
+
+        // This resigns for the human player; when both are
+        // human, the player whose turn it is resigns.
+        fn resign(me: &mut BoardView) -> EventResult {
+            let cell = {
+                let game = me.game.lock();
+                let next = game.get_next_move();
+                [next, Cell::Black, Cell::White]
+                    .iter()
+                    .copied()
+                    .find(|&cell| cell != Cell::Empty && game.get_player(cell).is_human())
+            };
+
+            if let Some(cell) = cell {
+                let _ = me.game.resign(cell);
+            }
+            Consumed(None)
+        }
+
+        fn toggle_hints(me: &mut BoardView) -> EventResult {
+            me.hints = match me.hints {
+                Hints::Off => Hints::Moves,
+                Hints::Moves => Hints::Flips,
+                Hints::Flips => Hints::Off,
+            };
+            Consumed(None)
+        }
+
+        // This offers a menu of levels, and sets the computer
+        // players to play at the level chosen.
+        fn choose_level(me: &mut BoardView) -> EventResult {
+            let game = me.game.clone();
+            let current = [Cell::Black, Cell::White].iter().find_map(|&cell| {
+                match game.lock().get_player(cell) {
+                    Player::Computer(search) => Some(search.get_level()),
+                    Player::Human | Player::MonteCarlo(_) => None,
+                }
+            });
+
+            let current = match current {
+                Some(current) => current,
+                None => {
+                    return EventResult::with_cb(|s| {
+                        s.add_layer(Dialog::info("Neither player has levels to choose from."));
+                    });
+                }
+            };
+
+            EventResult::with_cb(move |s| {
+                let selected = Level::ALL.iter().position(|&l| Some(l) == current);
+                let game = game.clone();
+                let menu = SelectView::new()
+                    .with_all(Level::ALL.iter().map(|&level| (level.to_string(), level)))
+                    .selected(selected.unwrap_or(0))
+                    .on_submit(move |s, &level| {
+                        set_level(&mut game.lock(), level);
+                        s.pop_layer();
+                    });
+
+                s.add_layer(
+                    Dialog::around(menu)
+                        .title("Computer level")
+                        .dismiss_button("Cancel"),
+                );
+            })
+        }
+
+        fn set_level(game: &mut Game, level: Level) {
+            for cell in [Cell::Black, Cell::White] {
+                if let Player::Computer(search) = game.get_player(cell) {
+                    let mut search = search.clone();
+                    search.set_level(level);
+                    game.set_player(cell, Player::Computer(search));
+                }
+            }
+        }
+
+        fn show_transcript(me: &mut BoardView) -> EventResult {
+            let transcript = me.game.lock().to_transcript().to_string();
+            EventResult::with_cb(move |s| {
+                s.add_layer(Dialog::info(transcript.clone()).title("Moves"));
+            })
+        }
+
+        fn save(me: &mut BoardView) -> EventResult {
+            let path = me.save_path.clone();
+            let result = File::create(&path).and_then(|file| {
+                let mut out = BufWriter::new(file);
+                me.game.lock().save(&mut out)?;
+                out.flush()
+            });
+
+            let message = match result {
+                Ok(()) => format!("Game saved to {}", path.display()),
+                Err(err) => format!("Could not save to {}: {}", path.display(), err),
+            };
+
+            EventResult::with_cb(move |s| {
+                s.add_layer(Dialog::info(message.clone()));
+            })
+        }
     }
 }
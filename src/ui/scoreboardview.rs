@@ -1,42 +1,52 @@
-use crate::game::*;
 use cursive::*;
+use rusthello_core::*;
 use std::cmp::*;
 
-/// A view to display the score, and when the game is over
-/// it declares the winner.
+/// A view to display the score and who is playing, and when the
+/// game is over it declares the winner. It also announces passes,
+/// and shows when the computer is thinking.
 pub struct ScoreboardView {
-    game: GameRef,
+    game: GameHandle,
 }
 
 impl ScoreboardView {
-    pub fn new(game: GameRef) -> ScoreboardView {
+    pub fn new(game: GameHandle) -> ScoreboardView {
         ScoreboardView { game }
     }
 }
 
 impl View for ScoreboardView {
     fn draw(&self, printer: &Printer) {
-        let game = self.game.borrow();
+        let game = self.game.lock();
         let board = game.to_board();
-        let game_over = board.is_game_over();
         let black_score = board.count_cells(Cell::Black);
         let white_score = board.count_cells(Cell::White);
 
-        let line1 = format!("●: {}", black_score);
+        let black = game.get_player(Cell::Black);
+        let white = game.get_player(Cell::White);
+
+        let line1 = format!("●: {:<3} {}", black_score, black);
         printer.print(Vec2::new(0, 0), &line1);
-        let line2 = format!("○: {}", white_score);
+        let line2 = format!("○: {:<3} {}", white_score, white);
         printer.print(Vec2::new(0, 1), &line2);
 
-        if game_over {
+        if game.is_over() {
             printer.print(Vec2::new(0, 2), "GAME OVER ");
 
-            let winner = match black_score.cmp(&white_score) {
-                Ordering::Greater => "● WINS",
-                Ordering::Less => "○ WINS",
-                Ordering::Equal => "DRAW",
+            let winner = match (game.get_resigned(), black_score.cmp(&white_score)) {
+                (Some(Cell::White), _) | (None, Ordering::Greater) => "● WINS",
+                (Some(_), _) | (None, Ordering::Less) => "○ WINS",
+                (None, Ordering::Equal) => "DRAW",
             };
 
             printer.print(Vec2::new(10, 2), winner)
+        } else if game.must_pass() && game.get_player(game.get_next_move()).is_human() {
+            printer.print(Vec2::new(0, 2), "You must pass");
+        } else if game.is_thinking() {
+            let name = game.get_next_move().name();
+            printer.print(Vec2::new(0, 2), &format!("{} is thinking…", name));
+        } else if let Some(passed) = game.get_last_pass() {
+            printer.print(Vec2::new(0, 2), &format!("{} passes", passed.name()));
         }
     }
 